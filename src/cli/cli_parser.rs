@@ -1,4 +1,11 @@
-use clap::{crate_authors, crate_description, crate_name, crate_version, Parser, Subcommand};
+use chrono::NaiveDate;
+
+use crate::core::sort::report::OutputFormat;
+use clap::{
+    crate_authors, crate_description, crate_name, crate_version, ArgAction, Args, Parser,
+    Subcommand,
+};
+use log::LevelFilter;
 
 #[derive(Parser)]
 #[command(
@@ -8,10 +15,30 @@ use clap::{crate_authors, crate_description, crate_name, crate_version, Parser,
     about = crate_description!(),
     long_about = None,
 )]
-
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    /// Show more: -v lists excluded entries, -vv explains every decision, -vvv traces
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+    /// Hide progress messages, only warnings and errors are reported
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+}
+
+impl Cli {
+    /// Level picked by -q/-v/-vv/-vvv, `None` leaves it to log_level
+    pub fn log_level(&self) -> Option<LevelFilter> {
+        if self.quiet {
+            return Some(LevelFilter::Warn);
+        }
+        match self.verbose {
+            0 => None,
+            1 => Some(LevelFilter::Info),
+            2 => Some(LevelFilter::Debug),
+            _ => Some(LevelFilter::Trace),
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -20,6 +47,58 @@ pub enum Commands {
     Sort {
         /// Directory to sort (optional unless target = "required").
         path: Option<String>,
+        /// Show what would be moved without touching the disk
+        #[arg(long = "dry-run", visible_aliases = ["sim", "simulate"])]
+        dry_run: bool,
+        /// Sort project folders (containing .git, Cargo.toml...) too
+        #[arg(long)]
+        force: bool,
+        /// Moves to run at the same time (default: `threads` from the config, or one per CPU)
+        #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: Option<u64>,
+        /// Sort protected paths (system folders, home, protected_paths) and sort into them
+        #[arg(long = "i-know-what-im-doing")]
+        allow_protected: bool,
+        /// Report format, json and ndjson are meant for scripts
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+    /// Keep a directory sorted, sorting new files as they arrive
+    Watch {
+        /// Directory to watch (optional unless target = "required").
+        path: Option<String>,
+        /// Seconds a new file must stay unchanged before it is sorted
+        #[arg(long, default_value_t = 2, value_name = "SECONDS")]
+        debounce: u64,
+        /// Show what would be moved without touching the disk
+        #[arg(long = "dry-run", visible_aliases = ["sim", "simulate"])]
+        dry_run: bool,
+        /// Sort project folders (containing .git, Cargo.toml...) too
+        #[arg(long)]
+        force: bool,
+        /// Moves to run at the same time (default: `threads` from the config, or one per CPU)
+        #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        jobs: Option<u64>,
+        /// Sort protected paths (system folders, home, protected_paths) and sort into them
+        #[arg(long = "i-know-what-im-doing")]
+        allow_protected: bool,
+    },
+    /// Revert a sort run (the most recent one if no id is given)
+    Undo {
+        /// Id of the run to revert, as printed at the end of `iris sort`
+        run_id: Option<String>,
+    },
+    /// Browse past sort runs
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+        #[command(flatten)]
+        filter: HistoryArgs,
+    },
+    /// Inspect the configured presets
+    Presets {
+        #[command(subcommand)]
+        action: PresetsAction,
     },
     /// Self-update iris
     Update,
@@ -43,6 +122,12 @@ pub enum Commands {
 
 #[derive(Subcommand, Debug)]
 pub enum ConfigAction {
+    /// Create the config file if missing and seed presets_path with the bundled presets
+    Init {
+        /// Overwrite preset files that already exist
+        #[arg(short, long)]
+        force: bool,
+    },
     /// Display the contents of config file
     Show,
     /// Edit the config file
@@ -55,6 +140,46 @@ pub enum ConfigAction {
     },
 }
 
+#[derive(Subcommand, Debug)]
+pub enum PresetsAction {
+    /// List extensions claimed by more than one enabled preset and which one wins
+    Conflicts,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryAction {
+    /// List every move of a sort run
+    Show {
+        /// Id of the run, as listed by `iris history`
+        run_id: String,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Only include runs on or after this date (YYYY-MM-DD)
+    #[arg(long, global = true, value_parser = parse_date)]
+    pub since: Option<NaiveDate>,
+    /// Only include runs on or before this date (YYYY-MM-DD)
+    #[arg(long, global = true, value_parser = parse_date)]
+    pub until: Option<NaiveDate>,
+    /// Only include moves made by this preset
+    #[arg(long, global = true)]
+    pub preset: Option<String>,
+    /// Only include moves whose source, destination or target contains this text
+    #[arg(long, global = true)]
+    pub path: Option<String>,
+    /// Print the history as JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+}
+
+/// Parse a YYYY-MM-DD date from the command line
+fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD", s))
+}
+
 #[cfg(target_os = "windows")]
 #[derive(Subcommand, Debug)]
 pub enum ContextAction {
@@ -96,4 +221,32 @@ mod tests {
         // this test verifies that CLI Structure is valid
         Cli::command().debug_assert();
     }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("2025-01-31").unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 31).unwrap()
+        );
+        assert!(parse_date("31/01/2025").is_err());
+    }
+
+    #[test]
+    fn test_log_level_flags() {
+        let level = |args: &[&str]| Cli::try_parse_from(args).map(|cli| cli.log_level());
+        assert_eq!(level(&["iris", "sort"]).unwrap(), None);
+        assert_eq!(
+            level(&["iris", "sort", "-v"]).unwrap(),
+            Some(LevelFilter::Info)
+        );
+        assert_eq!(
+            level(&["iris", "-vv", "sort"]).unwrap(),
+            Some(LevelFilter::Debug)
+        );
+        assert_eq!(
+            level(&["iris", "history", "-q"]).unwrap(),
+            Some(LevelFilter::Warn)
+        );
+        assert!(level(&["iris", "sort", "-q", "-v"]).is_err());
+    }
 }
@@ -51,7 +51,7 @@ fn install_windows(cmd: &mut clap::Command) {
     if is_powershell && !is_git_bash {
         if let Some(policy) = powershell_execution_policy() {
             if matches!(policy.as_str(), "Restricted" | "AllSigned") {
-                eprintln!(
+                log::error!(
                     "\nPowerShell execution policy is too restrictive ({policy})\n\
                     Completion scripts cannot be sourced.\n\n\
                     Fix:\n\
@@ -68,7 +68,7 @@ fn install_windows(cmd: &mut clap::Command) {
             let iris_completions = config_dir.join("Completions");
 
             if let Err(e) = fs::create_dir_all(&iris_completions) {
-                eprintln!("Failed to create completions directory: {}", e);
+                log::error!("Failed to create completions directory: {}", e);
                 return;
             }
 
@@ -76,7 +76,7 @@ fn install_windows(cmd: &mut clap::Command) {
             let mut file = match fs::File::create(&ps_file) {
                 Ok(f) => f,
                 Err(e) => {
-                    eprintln!("Failed to create completion file: {}", e);
+                    log::error!("Failed to create completion file: {}", e);
                     return;
                 }
             };
@@ -108,7 +108,7 @@ fn install_windows(cmd: &mut clap::Command) {
                         .expect("Failed to open profile for appending");
 
                     if let Err(e) = writeln!(file, "\n{}", source_line) {
-                        eprintln!(
+                        log::error!(
                             "Failed to append to profile {}: {}",
                             profile_path.display(),
                             e
@@ -176,7 +176,7 @@ fn install_unix(cmd: &mut clap::Command) {
     let completions_dir = config_dir.join("completions");
 
     if let Err(e) = fs::create_dir_all(&completions_dir) {
-        eprintln!("Failed to create completions directory: {}", e);
+        log::error!("Failed to create completions directory: {}", e);
         return;
     }
 
@@ -210,7 +210,7 @@ fn install_bash(cmd: &mut clap::Command, completions_dir: &std::path::Path) {
                 append_to_rc_file(&rc_file, &format!("source \"{}\"", bash_file.display()));
             }
         }
-        Err(e) => eprintln!("Failed to create completion file: {}", e),
+        Err(e) => log::error!("Failed to create completion file: {}", e),
     }
 }
 
@@ -231,7 +231,7 @@ fn install_zsh(cmd: &mut clap::Command, completions_dir: &std::path::Path) {
                 append_to_rc_file(&rc_file, &line);
             }
         }
-        Err(e) => eprintln!("Failed to create completion file: {}", e),
+        Err(e) => log::error!("Failed to create completion file: {}", e),
     }
 }
 
@@ -241,7 +241,7 @@ fn install_fish(cmd: &mut clap::Command) {
     if let Some(home) = dirs::home_dir() {
         let completions_dir = home.join(".config/fish/completions");
         if let Err(e) = fs::create_dir_all(&completions_dir) {
-            eprintln!("Failed to create fish completions directory: {}", e);
+            log::error!("Failed to create fish completions directory: {}", e);
             return;
         }
 
@@ -251,7 +251,7 @@ fn install_fish(cmd: &mut clap::Command) {
                 generate(Fish, cmd, "iris", &mut file);
                 println!("Installed Fish completion to: {}", fish_file.display());
             }
-            Err(e) => eprintln!("Failed to create completion file: {}", e),
+            Err(e) => log::error!("Failed to create completion file: {}", e),
         }
     }
 }
@@ -263,7 +263,7 @@ fn append_to_rc_file(path: &std::path::Path, content: &str) {
         if let Ok(mut file) = fs::File::create(path) {
             use std::io::Write;
             if let Err(e) = writeln!(file, "\n{}", content) {
-                eprintln!("Failed to write to {}: {}", path.display(), e);
+                log::error!("Failed to write to {}: {}", path.display(), e);
             } else {
                 println!(
                     "Added sourcing line to: {}",
@@ -285,7 +285,7 @@ fn append_to_rc_file(path: &std::path::Path, content: &str) {
         };
 
         let already_exists = existing_content.contains(content)
-            || fpath_prefix.map_or(false, |prefix| existing_content.contains(prefix));
+            || fpath_prefix.is_some_and(|prefix| existing_content.contains(prefix));
 
         if already_exists {
             println!(
@@ -296,16 +296,16 @@ fn append_to_rc_file(path: &std::path::Path, content: &str) {
         }
 
         use std::io::Write;
-        let mut file = match fs::OpenOptions::new().write(true).append(true).open(path) {
+        let mut file = match fs::OpenOptions::new().append(true).open(path) {
             Ok(f) => f,
             Err(e) => {
-                eprintln!("Failed to open {} for appending: {}", path.display(), e);
+                log::error!("Failed to open {} for appending: {}", path.display(), e);
                 return;
             }
         };
 
         if let Err(e) = writeln!(file, "\n{}", content) {
-            eprintln!("Failed to append to {}: {}", path.display(), e);
+            log::error!("Failed to append to {}: {}", path.display(), e);
         } else {
             println!(
                 "Added sourcing line to: {}",
@@ -333,7 +333,7 @@ fn uninstall_windows() {
     if ps_file.exists() {
         match fs::remove_file(&ps_file) {
             Ok(_) => println!("Removed completion file: {}", ps_file.display()),
-            Err(e) => eprintln!("Failed to remove completion file: {}", e),
+            Err(e) => log::error!("Failed to remove completion file: {}", e),
         }
     } else {
         println!("Completion file not found: {}", ps_file.display());
@@ -360,7 +360,7 @@ fn uninstall_windows() {
                                     "Removed sourcing line from PowerShell profile: {}",
                                     profile_path.display().to_string().as_str().cyan()
                                 ),
-                                Err(e) => eprintln!(
+                                Err(e) => log::error!(
                                     "Failed to write to profile {}: {}",
                                     profile_path.display(),
                                     e
@@ -373,7 +373,9 @@ fn uninstall_windows() {
                             );
                         }
                     }
-                    Err(e) => eprintln!("Failed to read profile {}: {}", profile_path.display(), e),
+                    Err(e) => {
+                        log::error!("Failed to read profile {}: {}", profile_path.display(), e)
+                    }
                 }
             }
         }
@@ -446,14 +448,15 @@ fn remove_line_from_file(path: &std::path::Path, partial_content: &str) {
                         "Removed sourcing line from: {}",
                         path.display().to_string().as_str().cyan()
                     ),
-                    Err(e) => eprintln!("Failed to write to {}: {}", path.display(), e),
+                    Err(e) => log::error!("Failed to write to {}: {}", path.display(), e),
                 }
             }
         }
-        Err(e) => eprintln!("Failed to read {}: {}", path.display(), e),
+        Err(e) => log::error!("Failed to read {}: {}", path.display(), e),
     }
 }
 
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
 fn powershell_execution_policy() -> Option<String> {
     for exe in ["pwsh", "powershell"] {
         let output = Command::new(exe)
@@ -473,6 +476,7 @@ fn powershell_execution_policy() -> Option<String> {
     None
 }
 
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
 fn get_powershell_profiles() -> Vec<PathBuf> {
     let mut profiles = Vec::new();
 
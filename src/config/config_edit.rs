@@ -1,3 +1,4 @@
+use crate::logging;
 use crate::paths::config_path::get_config_path;
 /// Edit the config file with `iris config edit`
 use edit::{edit_file, get_editor};
@@ -11,14 +12,16 @@ pub fn edit_config() -> Result<(), Box<dyn std::error::Error>> {
         .map(|n| n.to_string_lossy())
         .unwrap_or_else(|| editor_path.to_string_lossy());
 
-    println!(
-        "editing config file: {} with {}",
-        path.display(),
-        editor_name
-    );
+    if !logging::quiet() {
+        println!(
+            "editing config file: {} with {}",
+            path.display(),
+            editor_name
+        );
+    }
 
     if let Err(e) = edit_file(&path) {
-        eprintln!("editing failed: {}", e);
+        log::error!("editing failed: {}", e);
     }
 
     Ok(())
@@ -1,5 +1,50 @@
 /// Create a config file based on assets/defaults/iris.toml
-use std::path::PathBuf;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+
+use crate::config::config_parser::presets_dir;
+use crate::logging;
+use crate::paths::config_path::{get_config_dir, get_config_path};
+
+/// Preset files bundled from assets/defaults/presets, seeded into `presets_path`
+const BUNDLED_PRESETS: &[(&str, &str)] = &[
+    (
+        "archives.toml",
+        include_str!("../../assets/defaults/presets/archives.toml"),
+    ),
+    (
+        "code.toml",
+        include_str!("../../assets/defaults/presets/code.toml"),
+    ),
+    (
+        "data.toml",
+        include_str!("../../assets/defaults/presets/data.toml"),
+    ),
+    (
+        "design.toml",
+        include_str!("../../assets/defaults/presets/design.toml"),
+    ),
+    (
+        "documents.toml",
+        include_str!("../../assets/defaults/presets/documents.toml"),
+    ),
+    (
+        "images.toml",
+        include_str!("../../assets/defaults/presets/images.toml"),
+    ),
+    (
+        "media.toml",
+        include_str!("../../assets/defaults/presets/media.toml"),
+    ),
+    (
+        "misc.toml",
+        include_str!("../../assets/defaults/presets/misc.toml"),
+    ),
+    (
+        "system.toml",
+        include_str!("../../assets/defaults/presets/system.toml"),
+    ),
+];
 
 /// Capitalize the first letter of a given string
 fn capitalize_first(s: &str) -> String {
@@ -26,7 +71,7 @@ pub fn init_defaults(path: &PathBuf) -> std::io::Result<()> {
     let template = include_str!("../../assets/defaults/iris.toml");
     let content = template
         .replace("{project_name}", &project_name)
-        .replace("{version}", &version)
+        .replace("{version}", version)
         .replace("{author}", "lordaimer")
         .replace("{home_dir}", &home_dir);
 
@@ -37,6 +82,82 @@ pub fn init_defaults(path: &PathBuf) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Default presets directory, matching `presets_path = "./presets"` of the template
+pub fn default_presets_dir() -> PathBuf {
+    get_config_dir().join("presets")
+}
+
+/// Write the bundled preset files into `dir`, returning the files written.
+/// Existing files are kept unless `force` is set.
+pub fn init_presets(dir: &Path, force: bool) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for (name, content) in BUNDLED_PRESETS {
+        let path = dir.join(name);
+        if path.exists() && !force {
+            continue;
+        }
+        std::fs::write(&path, content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Create the config file if missing and seed `presets_path` with the bundled presets
+/// with `iris config init`
+pub fn init_config(force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = get_config_path();
+    if !config_path.exists() {
+        init_defaults(&config_path)?;
+        if !logging::quiet() {
+            println!(
+                "{}",
+                format!("Created config file: {}", config_path.display()).green()
+            );
+        }
+    } else if !logging::quiet() {
+        println!("Config file: {}", config_path.display());
+    }
+
+    // seed the configured presets_path, falling back to the default one
+    let dir = std::fs::read_to_string(&config_path)
+        .ok()
+        .and_then(|content| toml::from_str::<toml::Value>(&content).ok())
+        .and_then(|value| presets_dir(&value))
+        .unwrap_or_else(default_presets_dir);
+
+    let written = init_presets(&dir, force)?;
+    if logging::quiet() {
+        return Ok(());
+    }
+    for path in &written {
+        println!("{}", format!("    {}", path.display()).white());
+    }
+    let skipped = BUNDLED_PRESETS.len() - written.len();
+    println!(
+        "{}",
+        format!(
+            "Seeded {} preset file{} into {}",
+            written.len(),
+            if written.len() == 1 { "" } else { "s" },
+            dir.display()
+        )
+        .green()
+    );
+    if skipped > 0 {
+        println!(
+            "{}",
+            format!(
+                "{} existing preset file{} kept, use --force to overwrite",
+                skipped,
+                if skipped == 1 { " was" } else { "s were" }
+            )
+            .yellow()
+        );
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -70,6 +191,56 @@ mod tests {
         std::fs::remove_file(&tmp_file).unwrap();
     }
 
+    #[test]
+    fn test_bundled_presets_are_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let written = super::init_presets(dir.path(), false).unwrap();
+        assert_eq!(written.len(), super::BUNDLED_PRESETS.len());
+        // existing files are kept
+        assert!(super::init_presets(dir.path(), false).unwrap().is_empty());
+
+        // the template with the bundled presets merged in makes a valid config
+        let template = include_str!("../../assets/defaults/iris.toml");
+        let mut value: toml::Value = toml::from_str(template).unwrap();
+        crate::config::config_parser::merge_preset_files(&mut value, dir.path()).unwrap();
+        crate::config::config_validator::validate_config(&value).unwrap();
+    }
+
+    #[test]
+    fn test_bundled_presets_do_not_overlap() {
+        let dir = tempfile::tempdir().unwrap();
+        super::init_presets(dir.path(), false).unwrap();
+        let template = include_str!("../../assets/defaults/iris.toml");
+        let mut value: toml::Value = toml::from_str(template).unwrap();
+        let order =
+            crate::config::config_parser::merge_preset_files(&mut value, dir.path()).unwrap();
+        let config =
+            crate::config::config_processor::IrisConfig::from_value(&value, &order).unwrap();
+
+        // `iris presets conflicts` has nothing to report
+        let conflicts = crate::core::presets::presets::extension_conflicts(&config);
+        assert!(
+            conflicts.is_empty(),
+            "{:?}",
+            conflicts.iter().map(|c| &c.extension).collect::<Vec<_>>()
+        );
+
+        // every preset wins some extension, and only single extensions can match a file
+        let claims = config.extension_claims();
+        for preset in config.presets.iter().filter(|p| p.enabled) {
+            for ext in &preset.extension {
+                assert!(!ext.contains('.'), "{}: {}", preset.name, ext);
+            }
+            if preset.name != "dirs" {
+                assert!(
+                    claims.values().any(|c| c[0].name == preset.name),
+                    "{} wins no extension",
+                    preset.name
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_capitalize_first() {
         assert_eq!(super::capitalize_first("iris"), "Iris");
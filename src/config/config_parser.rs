@@ -1,9 +1,202 @@
 // Dynamically parse the contents of the main config file
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::config::config_processor::process_utils::resolve_path;
+use crate::config::config_validator::validate_preset_file;
 use crate::paths::config_path::get_config_path;
 
-// Parse the config file and pass it to the validator
-pub fn parse_config() -> Result<toml::Value, Box<dyn std::error::Error>> {
+/// The merged config and the order its presets were written in
+#[derive(Debug)]
+pub struct ParsedConfig {
+    pub value: Value,
+    // preset names in file order: iris.toml first, then the preset files alphabetically
+    pub preset_order: Vec<String>,
+}
+
+// Parse the config file, merge the presets of `presets_path` and pass it to the validator
+pub fn parse_config() -> Result<ParsedConfig, Box<dyn std::error::Error>> {
     let content = std::fs::read_to_string(get_config_path())?;
-    let value: toml::Value = toml::from_str(&content)?;
-    Ok(value)
+    let mut value: toml::Value = toml::from_str(&content)?;
+    let mut preset_order = preset_names_in_order(&content);
+    if let Some(dir) = presets_dir(&value) {
+        preset_order.extend(merge_preset_files(&mut value, &dir)?);
+    }
+    Ok(ParsedConfig {
+        value,
+        preset_order,
+    })
+}
+
+/// List the [preset.*] names of a TOML document in the order they are written.
+/// `toml::Value` tables are sorted alphabetically, `toml_edit` keeps the file order.
+pub fn preset_names_in_order(content: &str) -> Vec<String> {
+    let Ok(doc) = content.parse::<toml_edit::DocumentMut>() else {
+        return Vec::new();
+    };
+    doc.get("preset")
+        .and_then(|item| item.as_table_like())
+        .map(|presets| presets.iter().map(|(name, _)| name.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Resolve the `presets_path` of the [general] section, if set
+pub fn presets_dir(value: &Value) -> Option<PathBuf> {
+    value
+        .get("general")
+        .and_then(|g| g.get("presets_path"))
+        .and_then(Value::as_str)
+        .and_then(resolve_path)
+}
+
+/// Load every *.toml file of `dir` (alphabetically) and merge its presets into `value`,
+/// returning the names of the merged presets in file order.
+/// A preset of the main config replaces the one of the same name from a preset file,
+/// while the same name in two preset files is an error. A missing directory is not.
+pub fn merge_preset_files(
+    value: &mut Value,
+    dir: &Path,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    files.sort();
+
+    // names already defined by the main config take precedence over any preset file
+    let in_file: Vec<String> = value
+        .get("preset")
+        .and_then(Value::as_table)
+        .map(|t| t.keys().cloned().collect())
+        .unwrap_or_default();
+    let mut loaded_from: Vec<(String, PathBuf)> = Vec::new();
+    let mut merged = toml::Table::new();
+    let mut order: Vec<String> = Vec::new();
+
+    for file in files {
+        let content = fs::read_to_string(&file)
+            .map_err(|e| format!("failed to read preset file '{}': {}", file.display(), e))?;
+        let file_value: Value = toml::from_str(&content)
+            .map_err(|e| format!("failed to parse preset file '{}': {}", file.display(), e))?;
+        validate_preset_file(&file_value)
+            .map_err(|e| format!("preset file '{}' is invalid: {}", file.display(), e))?;
+
+        let Some(presets) = file_value.get("preset").and_then(Value::as_table) else {
+            continue;
+        };
+        for name in preset_names_in_order(&content) {
+            let Some(preset) = presets.get(&name) else {
+                continue;
+            };
+            if let Some((_, other)) = loaded_from.iter().find(|(n, _)| *n == name) {
+                return Err(format!(
+                    "preset '{}' in '{}' is already defined in '{}'",
+                    name,
+                    file.display(),
+                    other.display()
+                )
+                .into());
+            }
+            loaded_from.push((name.clone(), file.clone()));
+            if !in_file.contains(&name) {
+                merged.insert(name.clone(), preset.clone());
+                order.push(name);
+            }
+        }
+    }
+
+    if merged.is_empty() {
+        return Ok(order);
+    }
+    let Some(root) = value.as_table_mut() else {
+        return Ok(order);
+    };
+    // an invalid [preset] section is left as is and reported by the validator
+    if let Value::Table(presets) = root
+        .entry("preset")
+        .or_insert_with(|| Value::Table(toml::Table::new()))
+    {
+        presets.extend(merged);
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn preset(name: &str, path: &str) -> String {
+        format!(
+            "[preset.{}]\nenabled = true\nextension = [\"txt\"]\nrelative_path = \"{}\"\nabsolute_path = \"~/{}\"\n",
+            name, path, path
+        )
+    }
+
+    #[test]
+    fn test_merge_preset_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.toml"), preset("docs", "from-file")).unwrap();
+        fs::write(dir.path().join("b.toml"), preset("notes", "notes")).unwrap();
+        fs::write(dir.path().join("readme.md"), "not a preset").unwrap();
+
+        let mut value: Value = toml::from_str(&preset("docs", "from-main")).unwrap();
+        let order = merge_preset_files(&mut value, dir.path()).unwrap();
+        // the preset replaced by the main config keeps its place there
+        assert_eq!(order, vec!["notes"]);
+
+        let presets = value["preset"].as_table().unwrap();
+        assert_eq!(presets.len(), 2);
+        // the main config wins over the preset file
+        assert_eq!(presets["docs"]["relative_path"].as_str(), Some("from-main"));
+        assert!(presets.contains_key("notes"));
+    }
+
+    #[test]
+    fn test_merge_preset_files_errors_name_the_file() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.toml"), preset("docs", "docs")).unwrap();
+        fs::write(dir.path().join("b.toml"), preset("docs", "docs")).unwrap();
+        let mut value: Value = toml::from_str("").unwrap();
+        let err = merge_preset_files(&mut value, dir.path()).unwrap_err();
+        assert!(err.to_string().contains("b.toml"));
+
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("bad.toml"),
+            "[preset.docs]\nenabled = \"yes\"\n",
+        )
+        .unwrap();
+        let err = merge_preset_files(&mut value, dir.path()).unwrap_err();
+        assert!(err.to_string().contains("bad.toml"));
+    }
+
+    #[test]
+    fn test_preset_names_in_order() {
+        let content = format!(
+            "{}{}{}",
+            preset("zeta", "z"),
+            preset("alpha", "a"),
+            preset("mid", "m")
+        );
+        assert_eq!(
+            preset_names_in_order(&content),
+            vec!["zeta", "alpha", "mid"]
+        );
+    }
+
+    #[test]
+    fn test_missing_presets_dir_is_ignored() {
+        let dir = tempdir().unwrap();
+        let mut value: Value = toml::from_str("").unwrap();
+        merge_preset_files(&mut value, &dir.path().join("missing")).unwrap();
+        assert!(value.get("preset").is_none());
+    }
 }
@@ -0,0 +1,76 @@
+// This module compiles the glob and `regex:` patterns used by exclusion lists, shared by
+// the config validator and the sort engine
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+
+/// Prefix marking a pattern as a regular expression instead of a glob
+pub const REGEX_PREFIX: &str = "regex:";
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+/// A single compiled exclusion pattern
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    // globs containing a '/' match the path relative to the target instead of the name
+    match_path: bool,
+    matcher: Matcher,
+}
+
+impl Pattern {
+    /// Compile a glob, or a regex when prefixed with `regex:`
+    pub fn new(pattern: &str, case_sensitive: bool) -> Result<Self, String> {
+        if let Some(expr) = pattern.strip_prefix(REGEX_PREFIX) {
+            let regex = RegexBuilder::new(expr)
+                .case_insensitive(!case_sensitive)
+                .build()
+                .map_err(|e| format!("invalid regex '{}': {}", expr, e))?;
+            return Ok(Pattern {
+                source: pattern.to_string(),
+                match_path: true,
+                matcher: Matcher::Regex(regex),
+            });
+        }
+
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("invalid glob '{}': {}", pattern, e))?;
+        Ok(Pattern {
+            source: pattern.to_string(),
+            match_path: pattern.contains('/'),
+            matcher: Matcher::Glob(glob.compile_matcher()),
+        })
+    }
+
+    /// The pattern as written in the config
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Check the entry name, and its path relative to the target where it applies.
+    /// Regexes are tried against both, so `regex:^build/` and `regex:\.bak$` both work.
+    pub fn is_match(&self, name: &str, relative: &str) -> bool {
+        match &self.matcher {
+            Matcher::Glob(glob) if self.match_path => glob.is_match(relative),
+            Matcher::Glob(glob) => glob.is_match(name),
+            Matcher::Regex(regex) => regex.is_match(name) || regex.is_match(relative),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(Pattern::new("[abc", false).is_err());
+        assert!(Pattern::new("regex:(abc", false).is_err());
+    }
+}
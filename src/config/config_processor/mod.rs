@@ -1,26 +1,127 @@
+mod process_exclusions;
 #[allow(dead_code)]
 mod process_general;
+mod process_naming;
 mod process_presets;
-mod process_utils;
+pub(crate) mod process_utils;
 
+use process_exclusions::process_exclusions;
 use process_general::process_general;
+use process_naming::process_naming;
 use process_presets::process_presets;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 #[allow(dead_code)]
 pub struct IrisConfig {
     pub general: GeneralConfig,
     // if no presets exist in the global iris.toml file, presets will be an empty vector
     pub presets: Vec<PresetConfig>,
+    pub exclusions: ExclusionConfig,
+    pub naming: NamingConfig,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 #[allow(dead_code)]
 pub struct GeneralConfig {
     pub target: Option<Target>,
     pub mode: Mode,
     pub presets_path: Option<PathBuf>,
+    pub on_conflict: ConflictStrategy,
+    // naming pattern for renamed collisions, e.g. "{stem} ({n}).{ext}"
+    pub conflict_pattern: Option<String>,
+    // descend into subdirectories of the target (presets can override)
+    pub recursive: bool,
+    // deepest level to sort when recursive, 1 being the target itself
+    pub max_depth: Option<usize>,
+    // date used by the {year}/{month}/{day}/{week} placeholders
+    pub date_source: DateSource,
+    // sniff file headers so missing or wrong extensions still reach the right preset
+    pub detect_content: bool,
+    // names marking a project folder that is never sorted, `None` uses the defaults
+    pub project_markers: Option<Vec<String>>,
+    // never sorted nor sorted into, on top of the built-in system and home paths
+    pub protected_paths: Vec<ProtectedPath>,
+    // level of the diagnostics, `None` only shows warnings and errors
+    pub log_level: Option<log::LevelFilter>,
+    // also write the diagnostics to iris.log in the config directory
+    pub log_file: bool,
+    // moves running at the same time, `None` uses one per CPU
+    pub threads: Option<usize>,
+    // bytes per second shared by all copies between filesystems, `None` is unlimited
+    pub bandwidth_limit: Option<u64>,
+    // how a copy between filesystems is checked before the source is deleted
+    pub verify: VerifyMode,
+    // give copies between filesystems the times, owner, xattrs and permissions of the source
+    pub preserve_metadata: bool,
+    // what sorting does with symbolic links
+    pub symlinks: SymlinkPolicy,
+    // sort dot-prefixed and hidden entries, presets can opt in on their own
+    pub include_hidden: bool,
+}
+
+/// A path iris must never sort nor move files into
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtectedPath {
+    pub path: PathBuf,
+    pub scope: ProtectionScope,
+}
+
+/// How much of a protected path is protected
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ProtectionScope {
+    // only the path itself, its subfolders can be sorted
+    Exact,
+    // the path and everything below it
+    #[default]
+    Subtree,
+}
+
+impl std::str::FromStr for ProtectionScope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(ProtectionScope::Exact),
+            "subtree" => Ok(ProtectionScope::Subtree),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Settings from the [file_exclusions] section
+#[derive(Debug, Clone, Default)]
+pub struct ExclusionConfig {
+    // glob patterns, or regexes prefixed with "regex:", matched against every entry
+    pub patterns: Vec<String>,
+    // directory names that are never descended into or moved
+    pub directories: Vec<String>,
+    // match patterns and directory names case-sensitively
+    pub case_sensitive: bool,
+    // files outside these limits are never sorted
+    pub size: SizeLimits,
+    // files modified more recently than this are left for a later run
+    pub min_age: std::time::Duration,
+    // leave files another process has open (Linux only)
+    pub skip_open_files: bool,
+}
+
+/// `min_file_size` / `max_file_size` bounds in bytes, both inclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SizeLimits {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl SizeLimits {
+    pub fn is_unbounded(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    pub fn allows(&self, size: u64) -> bool {
+        self.min.is_none_or(|min| size >= min) && self.max.is_none_or(|max| size <= max)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -30,13 +131,216 @@ pub enum Target {
     CurrentDir,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum Mode {
+    #[default]
     Relative,
     Absolute,
 }
 
+/// What to do when the destination of a file already exists
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConflictStrategy {
+    // keep both, renaming the incoming file with the conflict pattern
+    #[default]
+    Rename,
+    Skip,
+    Overwrite,
+    OverwriteIfNewer,
+    KeepLarger,
+    // drop the incoming file if the existing one has identical content
+    Dedupe,
+}
+
+impl std::str::FromStr for ConflictStrategy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rename" => Ok(ConflictStrategy::Rename),
+            "skip" => Ok(ConflictStrategy::Skip),
+            "overwrite" => Ok(ConflictStrategy::Overwrite),
+            "overwrite_if_newer" => Ok(ConflictStrategy::OverwriteIfNewer),
+            "keep_larger" => Ok(ConflictStrategy::KeepLarger),
+            "dedupe" => Ok(ConflictStrategy::Dedupe),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Settings from the [file_naming] section
 #[derive(Debug, Clone)]
+pub struct NamingConfig {
+    // apply space_replacement and rename_method to destination names
+    pub rename_files: bool,
+    pub space_replacement: String,
+    pub rename_method: RenameMethod,
+    // strip characters that are invalid on Windows, on every platform
+    pub sanitize_names: bool,
+}
+
+impl Default for NamingConfig {
+    fn default() -> Self {
+        NamingConfig {
+            rename_files: false,
+            space_replacement: ".".to_string(),
+            rename_method: RenameMethod::default(),
+            sanitize_names: true,
+        }
+    }
+}
+
+impl NamingConfig {
+    /// The naming settings of a preset: its own keys, then the [file_naming] ones
+    pub fn with_overrides(&self, overrides: &NamingOverrides) -> NamingConfig {
+        NamingConfig {
+            rename_files: overrides.rename_files.unwrap_or(self.rename_files),
+            space_replacement: overrides
+                .space_replacement
+                .clone()
+                .unwrap_or_else(|| self.space_replacement.clone()),
+            rename_method: overrides.rename_method.unwrap_or(self.rename_method),
+            sanitize_names: overrides.sanitize_names.unwrap_or(self.sanitize_names),
+        }
+    }
+}
+
+/// [file_naming] keys set on a preset
+#[derive(Debug, Clone, Default)]
+pub struct NamingOverrides {
+    pub rename_files: Option<bool>,
+    pub space_replacement: Option<String>,
+    pub rename_method: Option<RenameMethod>,
+    pub sanitize_names: Option<bool>,
+}
+
+/// Case style applied to file names when rename_files is enabled
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum RenameMethod {
+    #[default]
+    Lower,
+    Upper,
+    Title,
+    Camel,
+    Pascal,
+    Snake,
+    Kebab,
+}
+
+impl std::str::FromStr for RenameMethod {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lower" => Ok(RenameMethod::Lower),
+            "upper" => Ok(RenameMethod::Upper),
+            "title" => Ok(RenameMethod::Title),
+            "camel" => Ok(RenameMethod::Camel),
+            "pascal" => Ok(RenameMethod::Pascal),
+            "snake" => Ok(RenameMethod::Snake),
+            "kebab" => Ok(RenameMethod::Kebab),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Where the date of the {year}/{month}/{day}/{week} placeholders comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DateSource {
+    #[default]
+    Modified,
+    // falls back to the modification time where the filesystem has no creation time
+    Created,
+    // the time of the sort
+    Now,
+}
+
+impl std::str::FromStr for DateSource {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "modified" => Ok(DateSource::Modified),
+            "created" => Ok(DateSource::Created),
+            "now" => Ok(DateSource::Now),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How a file copied to another filesystem is checked before its source is deleted
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum VerifyMode {
+    // trust the copy
+    None,
+    // compare the sizes
+    Size,
+    // compare the sizes and hashes of a few chunks, checking in full on mismatch
+    Sampled,
+    // compare the sizes and hashes of the whole files
+    #[default]
+    Full,
+}
+
+impl std::str::FromStr for VerifyMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(VerifyMode::None),
+            "size" => Ok(VerifyMode::Size),
+            "sampled" => Ok(VerifyMode::Sampled),
+            "full" => Ok(VerifyMode::Full),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What a sort does with the symbolic links it comes across
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SymlinkPolicy {
+    // leave links where they are
+    Skip,
+    // move the link itself, rewriting a relative target so it still points to the same place
+    #[default]
+    MoveLink,
+    // replace the link by a copy of what it points to, which stays where it is
+    Follow,
+}
+
+impl std::str::FromStr for SymlinkPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(SymlinkPolicy::Skip),
+            "move_link" => Ok(SymlinkPolicy::MoveLink),
+            "follow" => Ok(SymlinkPolicy::Follow),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Extension that makes a preset collect files no other preset matched
+pub const CATCH_ALL_EXTENSION: &str = "*";
+
+/// Every placeholder accepted in relative_path and absolute_path
+pub const PLACEHOLDERS: &[&str] = &[
+    "year",
+    "month",
+    "day",
+    "week",
+    "ext",
+    "preset",
+    "stem_initial",
+    "size_bucket",
+];
+
+/// Characters Windows does not allow in file names. They are stripped from renamed
+/// files on every platform so sorted files can be synced to any machine.
+pub const INVALID_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+#[derive(Debug, Clone, Default)]
 #[allow(dead_code)]
 pub struct PresetConfig {
     pub name: String,
@@ -44,13 +348,89 @@ pub struct PresetConfig {
     pub extension: Vec<String>,
     pub relative_path: Option<PathBuf>,
     pub absolute_path: Option<PathBuf>,
+    // overrides of the [general] conflict settings
+    pub on_conflict: Option<ConflictStrategy>,
+    pub conflict_pattern: Option<String>,
+    // overrides of the [general] recursion settings
+    pub recursive: Option<bool>,
+    pub max_depth: Option<usize>,
+    // collect files that have no extension at all
+    pub extensionless: bool,
+    // patterns excluded from this preset on top of [file_exclusions]
+    pub exclude: Vec<String>,
+    // presets with a higher priority win shared extensions, ties go to file order
+    pub priority: i64,
+    // [[preset.X.rule]] blocks, tried in order
+    pub rules: Vec<PresetRule>,
+    // files outside these limits fall through to the next preset claiming them
+    pub size: SizeLimits,
+    // overrides the [general] date_source
+    pub date_source: Option<DateSource>,
+    // overrides the [file_naming] settings
+    pub naming: NamingOverrides,
+    // MIME types like "image/png" or "image/*", matched when detect_content is on
+    pub mime: Vec<String>,
+    // overrides the [general] include_hidden
+    pub include_hidden: Option<bool>,
+}
+
+/// A [[preset.X.rule]] block sending matching files to a subfolder of the preset.
+/// Every condition that is set has to hold for the rule to match.
+#[derive(Debug, Clone, Default)]
+pub struct PresetRule {
+    pub extension: Vec<String>,
+    pub filename_regex: Option<String>,
+    pub filename_contains: Option<String>,
+    // applies to filename_contains and filename_regex
+    pub match_case: bool,
+    pub modified_within_days: Option<u64>,
+    pub size: SizeLimits,
+    // relative to the preset destination
+    pub folder: PathBuf,
 }
 
 impl IrisConfig {
-    pub fn from_value(value: &toml::Value) -> Result<Self, anyhow::Error> {
+    /// Build the config from the validated value. `preset_order` lists the preset
+    /// names in file order, which breaks priority ties between presets.
+    pub fn from_value(value: &toml::Value, preset_order: &[String]) -> Result<Self, anyhow::Error> {
         let general = process_general(value);
-        let presets = process_presets(value);
-        let config = IrisConfig { general, presets };
+        let mut presets = process_presets(value);
+        // presets are tried in this order, so the first one claiming an extension wins
+        let position = |name: &str| {
+            preset_order
+                .iter()
+                .position(|n| n == name)
+                .unwrap_or(usize::MAX)
+        };
+        presets.sort_by_key(|p| (std::cmp::Reverse(p.priority), position(&p.name)));
+        let exclusions = process_exclusions(value);
+        let naming = process_naming(value);
+        let config = IrisConfig {
+            general,
+            presets,
+            exclusions,
+            naming,
+        };
         Ok(config)
     }
+
+    /// The enabled presets claiming each extension (lowercased), in precedence order:
+    /// the first one wins, later ones only get files outside the size limits of the
+    /// earlier ones. The catch-all "*" is left out.
+    pub fn extension_claims(&self) -> BTreeMap<String, Vec<&PresetConfig>> {
+        let mut claims: BTreeMap<String, Vec<&PresetConfig>> = BTreeMap::new();
+        for preset in self.presets.iter().filter(|p| p.enabled) {
+            for ext in preset
+                .extension
+                .iter()
+                .filter(|e| *e != CATCH_ALL_EXTENSION)
+            {
+                let presets = claims.entry(ext.to_lowercase()).or_default();
+                if !presets.iter().any(|p| p.name == preset.name) {
+                    presets.push(preset);
+                }
+            }
+        }
+        claims
+    }
 }
@@ -0,0 +1,43 @@
+use super::{process_utils::size_limits, ExclusionConfig};
+use std::time::Duration;
+use toml::Value;
+
+fn string_array(table: &toml::Table, key: &str) -> Vec<String> {
+    table
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|arr| {
+            arr.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn process_exclusions(value: &Value) -> ExclusionConfig {
+    let Some(exclusions) = value.get("file_exclusions").and_then(Value::as_table) else {
+        return ExclusionConfig::default();
+    };
+
+    ExclusionConfig {
+        patterns: string_array(exclusions, "patterns"),
+        directories: string_array(exclusions, "directories"),
+        case_sensitive: exclusions
+            .get("case_sensitive")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        size: size_limits(exclusions),
+        min_age: Duration::from_secs(
+            exclusions
+                .get("min_age")
+                .and_then(Value::as_integer)
+                .unwrap_or(0)
+                .max(0) as u64,
+        ),
+        skip_open_files: exclusions
+            .get("skip_open_files")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+    }
+}
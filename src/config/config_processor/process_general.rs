@@ -1,4 +1,8 @@
-use super::{process_utils::resolve_path, GeneralConfig, Mode, Target};
+use super::{
+    process_utils::{parse_size, resolve_path},
+    ConflictStrategy, DateSource, GeneralConfig, Mode, ProtectedPath, ProtectionScope,
+    SymlinkPolicy, Target, VerifyMode,
+};
 use toml::Value;
 
 pub fn process_general(value: &Value) -> GeneralConfig {
@@ -28,9 +32,137 @@ pub fn process_general(value: &Value) -> GeneralConfig {
         .map(resolve_path)
         .unwrap_or_else(|| None);
 
+    let on_conflict = general
+        .get("on_conflict")
+        .and_then(Value::as_str)
+        .and_then(|s| s.parse::<ConflictStrategy>().ok())
+        .unwrap_or_default();
+
+    let conflict_pattern = general
+        .get("conflict_pattern")
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string);
+
+    let recursive = general
+        .get("recursive")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    let max_depth = general
+        .get("max_depth")
+        .and_then(Value::as_integer)
+        .map(|d| d as usize);
+
+    let date_source = general
+        .get("date_source")
+        .and_then(Value::as_str)
+        .and_then(|s| s.parse::<DateSource>().ok())
+        .unwrap_or_default();
+
+    let detect_content = general
+        .get("detect_content")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    let project_markers = general
+        .get("project_markers")
+        .and_then(Value::as_array)
+        .map(|arr| {
+            arr.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        });
+
+    let protected_paths = general
+        .get("protected_paths")
+        .and_then(Value::as_array)
+        .map(|arr| arr.iter().filter_map(process_protected_path).collect())
+        .unwrap_or_default();
+
+    let log_level = general
+        .get("log_level")
+        .and_then(Value::as_str)
+        .and_then(|s| s.parse::<log::LevelFilter>().ok());
+
+    let log_file = general
+        .get("log_file")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    let threads = general
+        .get("threads")
+        .and_then(Value::as_integer)
+        .map(|n| n as usize);
+
+    // 0 means no limit
+    let bandwidth_limit = general
+        .get("bandwidth_limit")
+        .and_then(|v| parse_size(v).ok())
+        .filter(|limit| *limit > 0);
+
+    let verify = general
+        .get("verify")
+        .and_then(Value::as_str)
+        .and_then(|s| s.parse::<VerifyMode>().ok())
+        .unwrap_or_default();
+
+    let preserve_metadata = general
+        .get("preserve_metadata")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+
+    let symlinks = general
+        .get("symlinks")
+        .and_then(Value::as_str)
+        .and_then(|s| s.parse::<SymlinkPolicy>().ok())
+        .unwrap_or_default();
+
+    let include_hidden = general
+        .get("include_hidden")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
     GeneralConfig {
         target,
         mode,
         presets_path,
+        on_conflict,
+        conflict_pattern,
+        recursive,
+        max_depth,
+        date_source,
+        detect_content,
+        project_markers,
+        protected_paths,
+        log_level,
+        log_file,
+        threads,
+        bandwidth_limit,
+        verify,
+        preserve_metadata,
+        symlinks,
+        include_hidden,
     }
 }
+
+/// A `protected_paths` entry: a path (protecting its subtree) or { path, scope }
+fn process_protected_path(value: &Value) -> Option<ProtectedPath> {
+    let (path, scope) = match value {
+        Value::String(path) => (path.as_str(), ProtectionScope::default()),
+        Value::Table(table) => (
+            table.get("path")?.as_str()?,
+            table
+                .get("scope")
+                .and_then(Value::as_str)
+                .and_then(|s| s.parse::<ProtectionScope>().ok())
+                .unwrap_or_default(),
+        ),
+        _ => return None,
+    };
+    Some(ProtectedPath {
+        path: resolve_path(path)?,
+        scope,
+    })
+}
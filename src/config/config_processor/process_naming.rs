@@ -0,0 +1,27 @@
+use super::{NamingConfig, NamingOverrides, RenameMethod};
+use toml::Value;
+
+pub fn process_naming(value: &Value) -> NamingConfig {
+    let overrides = value
+        .get("file_naming")
+        .and_then(Value::as_table)
+        .map(naming_overrides)
+        .unwrap_or_default();
+    NamingConfig::default().with_overrides(&overrides)
+}
+
+/// Read the [file_naming] keys of a table, the section itself or a preset
+pub fn naming_overrides(table: &toml::Table) -> NamingOverrides {
+    NamingOverrides {
+        rename_files: table.get("rename_files").and_then(Value::as_bool),
+        space_replacement: table
+            .get("space_replacement")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        rename_method: table
+            .get("rename_method")
+            .and_then(Value::as_str)
+            .and_then(|s| s.parse::<RenameMethod>().ok()),
+        sanitize_names: table.get("sanitize_names").and_then(Value::as_bool),
+    }
+}
@@ -1,5 +1,6 @@
-use super::PresetConfig;
-use crate::config::config_processor::process_utils::resolve_path;
+use super::process_naming::naming_overrides;
+use super::{ConflictStrategy, DateSource, PresetConfig, PresetRule};
+use crate::config::config_processor::process_utils::{resolve_path, size_limits};
 use toml::Value;
 pub fn process_presets(value: &Value) -> Vec<PresetConfig> {
     let mut presets = Vec::new();
@@ -33,8 +34,96 @@ pub fn process_presets(value: &Value) -> Vec<PresetConfig> {
                 .filter(|s| !s.is_empty()) // filter out empty strings
                 .map(resolve_path)
                 .unwrap_or_else(|| None),
+            on_conflict: table
+                .get("on_conflict")
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse::<ConflictStrategy>().ok()),
+            conflict_pattern: table
+                .get("conflict_pattern")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(str::to_string),
+            recursive: table.get("recursive").and_then(|v| v.as_bool()),
+            max_depth: table
+                .get("max_depth")
+                .and_then(|v| v.as_integer())
+                .map(|d| d as usize),
+            extensionless: table
+                .get("extensionless")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            exclude: table
+                .get("exclude")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            priority: table
+                .get("priority")
+                .and_then(|v| v.as_integer())
+                .unwrap_or(0),
+            rules: table
+                .get("rule")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(process_rule).collect())
+                .unwrap_or_default(),
+            size: size_limits(table),
+            date_source: table
+                .get("date_source")
+                .and_then(|v| v.as_str())
+                .and_then(|s| s.parse::<DateSource>().ok()),
+            naming: naming_overrides(table),
+            mime: table
+                .get("mime")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            include_hidden: table.get("include_hidden").and_then(|v| v.as_bool()),
         });
     }
 
     presets
 }
+
+fn process_rule(value: &Value) -> Option<PresetRule> {
+    let rule = value.as_table()?;
+    let string = |key: &str| {
+        rule.get(key)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    Some(PresetRule {
+        extension: rule
+            .get("extension")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(str::to_lowercase)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        filename_regex: string("filename_regex"),
+        filename_contains: string("filename_contains"),
+        match_case: rule
+            .get("match_case")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        modified_within_days: rule
+            .get("modified_within_days")
+            .and_then(|v| v.as_integer())
+            .map(|d| d as u64),
+        size: size_limits(rule),
+        folder: string("folder")?.into(),
+    })
+}
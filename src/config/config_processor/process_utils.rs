@@ -1,13 +1,15 @@
+use super::SizeLimits;
 use crate::paths::config_path::get_config_path;
 use std::path::PathBuf;
+use toml::Value;
 
 pub fn resolve_path(path_str: &str) -> Option<PathBuf> {
     if path_str.trim().is_empty() {
         return None;
     }
 
-    let mut path = if path_str.starts_with("~/") {
-        dirs::home_dir()?.join(&path_str[2..])
+    let mut path = if let Some(rest) = path_str.strip_prefix("~/") {
+        dirs::home_dir()?.join(rest)
     } else if path_str.starts_with("./") {
         let config_path = get_config_path();
         let config_dir = config_path.parent()?;
@@ -34,3 +36,66 @@ pub fn resolve_path(path_str: &str) -> Option<PathBuf> {
 
     Some(path)
 }
+
+/// Parse a file size given in bytes (`1024`) or with a unit (`10MB`, `1.5GiB`).
+/// KB/MB/GB/TB are powers of 1000, KiB/MiB/GiB/TiB powers of 1024.
+pub fn parse_size(value: &Value) -> Result<u64, String> {
+    let text = match value {
+        Value::Integer(n) if *n >= 0 => return Ok(*n as u64),
+        Value::String(s) => s.trim(),
+        _ => return Err(format!("{} is not a file size", value)),
+    };
+
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a file size", text))?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1_000,
+        "m" | "mb" => 1_000_000,
+        "g" | "gb" => 1_000_000_000,
+        "t" | "tb" => 1_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => return Err(format!("unknown size unit in '{}'", text)),
+    };
+    Ok((number * multiplier as f64).round() as u64)
+}
+
+/// Read `min_file_size` / `max_file_size` from a table. 0 means no limit.
+pub fn size_limits(table: &toml::Table) -> SizeLimits {
+    let limit = |key: &str| {
+        table
+            .get(key)
+            .and_then(|v| parse_size(v).ok())
+            .filter(|size| *size > 0)
+    };
+    SizeLimits {
+        min: limit("min_file_size"),
+        max: limit("max_file_size"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        let size = |s: &str| parse_size(&Value::String(s.to_string()));
+        assert_eq!(parse_size(&Value::Integer(1024)), Ok(1024));
+        assert_eq!(size("10MB"), Ok(10_000_000));
+        assert_eq!(size("1.5GiB"), Ok(1_610_612_736));
+        assert_eq!(size("512 kib"), Ok(524_288));
+        assert_eq!(size("2 GB"), Ok(2_000_000_000));
+        assert!(size("10 parsecs").is_err());
+        assert!(size("MB").is_err());
+        assert!(parse_size(&Value::Integer(-1)).is_err());
+    }
+}
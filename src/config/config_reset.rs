@@ -1,4 +1,5 @@
-use crate::config::config_init::init_defaults;
+use crate::config::config_init::{default_presets_dir, init_defaults, init_presets};
+use crate::logging;
 use crate::paths::config_path::get_config_path;
 use colored::Colorize;
 /// Reset the config file to defaults with `iris config reset`
@@ -12,8 +13,7 @@ pub fn reset_config(noconfirm: bool) -> Result<(), Box<dyn std::error::Error>> {
             return Ok(());
         }
     } else {
-        // TODO: print this only if log level: verbose
-        println!("{}", "Skipping confirmation...".green());
+        log::info!("Skipping confirmation...");
     }
 
     let config_path = get_config_path();
@@ -21,8 +21,12 @@ pub fn reset_config(noconfirm: bool) -> Result<(), Box<dyn std::error::Error>> {
         std::fs::remove_file(&config_path)?;
     }
     if let Ok(()) = init_defaults(&config_path) {
-        println!("{}", "Successfully reset the config file.".green());
+        if !logging::quiet() {
+            println!("{}", "Successfully reset the config file.".green());
+        }
     };
+    // the default config has no presets of its own, restore the bundled ones if missing
+    init_presets(&default_presets_dir(), false)?;
     Ok(())
 }
 
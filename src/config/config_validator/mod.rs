@@ -1,10 +1,18 @@
 #[cfg(test)]
 mod tests;
+mod validate_exclusions;
 mod validate_general;
+mod validate_naming;
 mod validate_presets;
 
+use crate::config::config_pattern::Pattern;
+use crate::config::config_processor::process_utils::parse_size;
+use crate::config::config_processor::PLACEHOLDERS;
 use toml::Value;
+use validate_exclusions::validate_exclusions;
 use validate_general::validate_general;
+use validate_naming::validate_naming;
+pub use validate_presets::validate_preset_file;
 use validate_presets::validate_presets;
 
 #[derive(Debug)]
@@ -59,8 +67,169 @@ impl std::fmt::Display for ValidationError {
 // Implement the error trait for ConfigError
 impl std::error::Error for ValidationError {}
 
+/// Accepted values of the `on_conflict` key in [general] and in presets
+pub(super) const CONFLICT_STRATEGIES: &[&str] = &[
+    "rename",
+    "skip",
+    "overwrite",
+    "overwrite_if_newer",
+    "keep_larger",
+    "dedupe",
+];
+
+/// Accepted values of the `date_source` key in [general] and in presets
+pub(super) const DATE_SOURCES: &[&str] = &["modified", "created", "now"];
+
+/// Accepted values of the `log_level` key in [general]
+pub(super) const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+/// Accepted values of the `verify` key in [general]
+pub(super) const VERIFY_MODES: &[&str] = &["none", "size", "sampled", "full"];
+
+/// Accepted values of the `symlinks` key in [general]
+pub(super) const SYMLINK_POLICIES: &[&str] = &["skip", "move_link", "follow"];
+
+/// Accepted values of the `scope` of a protected_paths entry
+pub(super) const PROTECTION_SCOPES: &[&str] = &["exact", "subtree"];
+
+/// Return the first `{name}` of the template that is not a known placeholder
+fn unknown_placeholder(template: &str) -> Option<String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Some(rest[start..].to_string());
+        };
+        let name = &rest[start + 1..start + len];
+        if !PLACEHOLDERS.contains(&name) {
+            return Some(format!("{{{}}}", name));
+        }
+        rest = &rest[start + len + 1..];
+    }
+    None
+}
+
+/// Validate a `relative_path` or `absolute_path` value: a string whose placeholders are all known
+pub(super) fn validate_dest_path(key: &str, value: &Value) -> Result<(), ValidationError> {
+    let Some(path) = value.as_str() else {
+        return Err(ValidationError::InvalidValue {
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+    };
+    match unknown_placeholder(path) {
+        Some(placeholder) => Err(ValidationError::InvalidValue {
+            key: key.to_string(),
+            value: format!(
+                "{} (unknown placeholder {}, expected one of {})",
+                path,
+                placeholder,
+                PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("{{{}}}", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Validate a `conflict_pattern` value. it must be a string containing the `{n}` counter
+/// (otherwise every renamed collision would produce the same name)
+pub(super) fn validate_conflict_pattern(key: &str, value: &Value) -> Result<(), ValidationError> {
+    match value.as_str() {
+        Some(s) if s.contains("{n}") => Ok(()),
+        _ => Err(ValidationError::InvalidValue {
+            key: key.to_string(),
+            value: format!("{} (must be a string containing {{n}})", value),
+        }),
+    }
+}
+
+/// Validate that a value is a boolean
+pub(super) fn validate_bool(key: &str, value: &Value) -> Result<(), ValidationError> {
+    if value.is_bool() {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidValue {
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Validate that a value is an integer of at least 1
+pub(super) fn validate_positive_integer(key: &str, value: &Value) -> Result<(), ValidationError> {
+    match value.as_integer() {
+        Some(n) if n >= 1 => Ok(()),
+        _ => Err(ValidationError::InvalidValue {
+            key: key.to_string(),
+            value: format!("{} (must be an integer of at least 1)", value),
+        }),
+    }
+}
+
+/// Validate that a value is an array made only of strings
+pub(super) fn validate_string_array(key: &str, value: &Value) -> Result<(), ValidationError> {
+    match value.as_array() {
+        Some(arr) if arr.iter().all(Value::is_str) => Ok(()),
+        Some(_) => Err(ValidationError::InvalidValue {
+            key: key.to_string(),
+            value: "array must contain only strings".to_string(),
+        }),
+        None => Err(ValidationError::InvalidValue {
+            key: key.to_string(),
+            value: "must be an array".to_string(),
+        }),
+    }
+}
+
+/// Validate an array of exclusion patterns, compiling each glob or `regex:` pattern
+pub(super) fn validate_patterns(key: &str, value: &Value) -> Result<(), ValidationError> {
+    validate_string_array(key, value)?;
+    for pattern in value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        // case sensitivity does not affect whether a pattern compiles
+        Pattern::new(pattern, false).map_err(|e| ValidationError::InvalidValue {
+            key: key.to_string(),
+            value: e,
+        })?;
+    }
+    Ok(())
+}
+
+/// Validate `min_file_size` / `max_file_size` of a table (bytes or sizes like "10MB")
+pub(super) fn validate_size_limits(table: &toml::Table) -> Result<(), ValidationError> {
+    let mut limits = [None, None];
+    for (limit, key) in limits.iter_mut().zip(["min_file_size", "max_file_size"]) {
+        if let Some(value) = table.get(key) {
+            let size = parse_size(value).map_err(|e| ValidationError::InvalidValue {
+                key: key.to_string(),
+                value: e,
+            })?;
+            // 0 means no limit
+            *limit = Some(size).filter(|s| *s > 0);
+        }
+    }
+    if let [Some(min), Some(max)] = limits {
+        if min > max {
+            return Err(ValidationError::InvalidValue {
+                key: "min_file_size".to_string(),
+                value: format!("{} (larger than max_file_size)", min),
+            });
+        }
+    }
+    Ok(())
+}
+
 pub fn validate_config(value: &Value) -> Result<(), ValidationError> {
     validate_general(value)?;
     validate_presets(value)?;
+    validate_exclusions(value)?;
+    validate_naming(value)?;
     Ok(())
 }
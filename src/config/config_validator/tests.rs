@@ -27,6 +27,141 @@ presets_path = "./presets"
     validate_general(&parse_toml(full)).unwrap();
 }
 
+#[test]
+fn general_conflict_settings() {
+    let valid = r#"
+[general]
+mode = "relative"
+on_conflict = "keep_larger"
+conflict_pattern = "{stem} ({n}).{ext}"
+"#;
+    validate_general(&parse_toml(valid)).unwrap();
+
+    let bad_strategy = r#"
+[general]
+mode = "relative"
+on_conflict = "merge"
+"#;
+    assert!(matches!(
+        validate_general(&parse_toml(bad_strategy)),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+
+    // without {n} every renamed collision would get the same name
+    let bad_pattern = r#"
+[general]
+mode = "relative"
+conflict_pattern = "{stem}-copy.{ext}"
+"#;
+    assert!(matches!(
+        validate_general(&parse_toml(bad_pattern)),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+}
+
+#[test]
+fn general_recursion_settings() {
+    let valid = r#"
+[general]
+mode = "relative"
+recursive = true
+max_depth = 3
+include_hidden = true
+"#;
+    validate_general(&parse_toml(valid)).unwrap();
+
+    for invalid in [
+        "recursive = \"yes\"",
+        "max_depth = 0",
+        "max_depth = \"3\"",
+        "include_hidden = 1",
+    ] {
+        let toml = format!("[general]\nmode = \"relative\"\n{}\n", invalid);
+        assert!(matches!(
+            validate_general(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+}
+
+#[test]
+fn general_protected_paths() {
+    let valid = r#"
+[general]
+mode = "relative"
+protected_paths = ["~/Projects", { path = "/data", scope = "exact" }, { path = "/srv" }]
+"#;
+    validate_general(&parse_toml(valid)).unwrap();
+
+    for invalid in [
+        "protected_paths = \"~/Projects\"",
+        "protected_paths = [\"\"]",
+        "protected_paths = [{ path = \"/data\", scope = \"tree\" }]",
+        "protected_paths = [{ scope = \"exact\" }]",
+        "protected_paths = [{ path = \"/data\", recursive = true }]",
+    ] {
+        let toml = format!("[general]\nmode = \"relative\"\n{}\n", invalid);
+        assert!(matches!(
+            validate_general(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+}
+
+#[test]
+fn general_logging() {
+    let valid = r#"
+[general]
+mode = "relative"
+log_level = "debug"
+log_file = true
+"#;
+    validate_general(&parse_toml(valid)).unwrap();
+
+    for invalid in [
+        "log_level = \"verbose\"",
+        "log_level = 2",
+        "log_file = \"yes\"",
+    ] {
+        let toml = format!("[general]\nmode = \"relative\"\n{}\n", invalid);
+        assert!(matches!(
+            validate_general(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+}
+
+#[test]
+fn general_copy_settings() {
+    let valid = r#"
+[general]
+mode = "relative"
+threads = 2
+bandwidth_limit = "20MB"
+verify = "sampled"
+preserve_metadata = false
+symlinks = "follow"
+"#;
+    validate_general(&parse_toml(valid)).unwrap();
+
+    for invalid in [
+        "threads = 0",
+        "threads = \"4\"",
+        "bandwidth_limit = \"fast\"",
+        "bandwidth_limit = -1",
+        "verify = \"quick\"",
+        "verify = true",
+        "preserve_metadata = \"yes\"",
+        "symlinks = \"move\"",
+    ] {
+        let toml = format!("[general]\nmode = \"relative\"\n{}\n", invalid);
+        assert!(matches!(
+            validate_general(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+}
+
 #[test]
 fn general_missing_required_key() {
     let toml = r#"
@@ -138,6 +273,358 @@ fn presets_extra_invalid_key() {
     ));
 }
 
+#[test]
+fn presets_conflict_override() {
+    let valid = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["txt"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+        on_conflict = "dedupe"
+        conflict_pattern = "{stem}_{n}.{ext}"
+    "#;
+    validate_presets(&parse_toml(valid)).unwrap();
+
+    let invalid = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["txt"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+        on_conflict = true
+    "#;
+    assert!(matches!(
+        validate_presets(&parse_toml(invalid)),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+}
+
+#[test]
+fn presets_recursion_override() {
+    let valid = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["txt"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+        recursive = true
+        max_depth = 2
+    "#;
+    validate_presets(&parse_toml(valid)).unwrap();
+}
+
+#[test]
+fn presets_include_hidden() {
+    let valid = r#"
+        [preset.calendar]
+        enabled = true
+        extension = ["ics"]
+        relative_path = "calendar"
+        absolute_path = "~/Calendar"
+        include_hidden = true
+    "#;
+    validate_presets(&parse_toml(valid)).unwrap();
+
+    let invalid = valid.replace("include_hidden = true", "include_hidden = \"yes\"");
+    assert!(matches!(
+        validate_presets(&parse_toml(&invalid)),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+}
+
+#[test]
+fn presets_catch_all_and_extensionless() {
+    let valid = r#"
+        [preset.misc]
+        enabled = true
+        extension = ["*"]
+        relative_path = "misc"
+        absolute_path = "~/Misc"
+
+        [preset.no_extension]
+        enabled = true
+        extensionless = true
+        relative_path = "misc/no_extension"
+        absolute_path = "~/Misc/no_extension"
+    "#;
+    validate_presets(&parse_toml(valid)).unwrap();
+
+    let invalid = r#"
+        [preset.misc]
+        enabled = true
+        extensionless = "yes"
+        relative_path = "misc"
+        absolute_path = "~/Misc"
+    "#;
+    assert!(matches!(
+        validate_presets(&parse_toml(invalid)),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+}
+
+#[test]
+fn presets_priority() {
+    let valid = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["txt"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+        priority = -2
+    "#;
+    validate_presets(&parse_toml(valid)).unwrap();
+
+    let invalid = valid.replace("-2", "\"high\"");
+    assert!(matches!(
+        validate_presets(&parse_toml(&invalid)),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+}
+
+#[test]
+fn presets_rules() {
+    let valid = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["pdf"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+
+        [[preset.docs.rule]]
+        extension = ["pdf"]
+        modified_within_days = 7
+        folder = "pdf/recent"
+
+        [[preset.docs.rule]]
+        filename_regex = ".*report_[0-9]{4}.*\\.pdf"
+        filename_contains = "invoice"
+        match_case = true
+        folder = "pdf/reports"
+    "#;
+    validate_presets(&parse_toml(valid)).unwrap();
+
+    for (rule, expected_key) in [
+        ("filename_regex = \"(\"\nfolder = \"x\"", "filename_regex"),
+        ("folder = \"../outside\"", "folder"),
+        (
+            "modified_within_days = 0\nfolder = \"x\"",
+            "modified_within_days",
+        ),
+    ] {
+        let toml = format!(
+            "[preset.docs]\nenabled = true\nextension = [\"pdf\"]\nrelative_path = \"docs\"\nabsolute_path = \"/docs\"\n[[preset.docs.rule]]\n{}\n",
+            rule
+        );
+        match validate_presets(&parse_toml(&toml)) {
+            Err(ValidationError::InvalidValue { key, value: _ }) => assert_eq!(key, expected_key),
+            other => panic!("unexpected result for {}: {:?}", rule, other),
+        }
+    }
+
+    let missing_folder = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["pdf"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+
+        [[preset.docs.rule]]
+        filename_contains = "invoice"
+    "#;
+    assert!(matches!(
+        validate_presets(&parse_toml(missing_folder)),
+        Err(ValidationError::MissingKey { key: _, section: _ })
+    ));
+}
+
+// ===== FILE EXCLUSIONS SECTION TESTS =====
+#[test]
+fn exclusions_directories() {
+    validate_exclusions(&parse_toml("")).unwrap();
+    validate_exclusions(&parse_toml(
+        r#"
+        [file_exclusions]
+        patterns = ["*.tmp", "regex:^~\\$"]
+        directories = [".git", "node_modules"]
+        case_sensitive = true
+    "#,
+    ))
+    .unwrap();
+
+    assert!(matches!(
+        validate_exclusions(&parse_toml(
+            r#"
+            [file_exclusions]
+            directories = ".git"
+        "#
+        )),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+    assert!(matches!(
+        validate_exclusions(&parse_toml(
+            r#"
+            [file_exclusions]
+            unknown = true
+        "#
+        )),
+        Err(ValidationError::InvalidKey { preset: _, key: _ })
+    ));
+    for invalid in [r#"patterns = ["[abc"]"#, r#"patterns = ["regex:(abc"]"#] {
+        let toml = format!("[file_exclusions]\n{}\n", invalid);
+        assert!(matches!(
+            validate_exclusions(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+}
+
+#[test]
+fn file_size_limits() {
+    validate_exclusions(&parse_toml(
+        r#"
+        [file_exclusions]
+        min_file_size = 1024
+        max_file_size = "1.5GiB"
+    "#,
+    ))
+    .unwrap();
+
+    for invalid in [
+        "min_file_size = \"10 parsecs\"",
+        "max_file_size = -1",
+        "min_file_size = \"2GB\"\nmax_file_size = \"1GB\"",
+    ] {
+        let toml = format!("[file_exclusions]\n{}\n", invalid);
+        assert!(matches!(
+            validate_exclusions(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+
+    let presets = r#"
+        [preset.large_videos]
+        enabled = true
+        extension = ["mp4"]
+        relative_path = "videos/large"
+        absolute_path = "/mnt/storage/videos"
+        min_file_size = "2GB"
+
+        [[preset.large_videos.rule]]
+        max_file_size = "4GB"
+        folder = "under-4gb"
+    "#;
+    validate_presets(&parse_toml(presets)).unwrap();
+}
+
+#[test]
+fn unknown_placeholder_is_reported() {
+    assert_eq!(unknown_placeholder("photos/{year}/{month}"), None);
+    assert_eq!(
+        unknown_placeholder("photos/{yaer}"),
+        Some("{yaer}".to_string())
+    );
+    assert_eq!(
+        unknown_placeholder("photos/{year"),
+        Some("{year".to_string())
+    );
+}
+
+#[test]
+fn presets_destination_placeholders() {
+    let valid = r#"
+        [general]
+        mode = "relative"
+        date_source = "created"
+
+        [preset.photos]
+        enabled = true
+        extension = ["jpg"]
+        relative_path = "photos/{year}/{month}"
+        absolute_path = "~/Pictures/{year}/{stem_initial}"
+        date_source = "now"
+    "#;
+    validate_general(&parse_toml(valid)).unwrap();
+    validate_presets(&parse_toml(valid)).unwrap();
+
+    for (relative_path, date_source) in [
+        ("photos/{yaer}", "modified"),
+        ("photos/{year", "modified"),
+        ("photos", "accessed"),
+    ] {
+        let toml = format!(
+            "[preset.photos]\nenabled = true\nextension = [\"jpg\"]\nabsolute_path = \"/p\"\n\
+             relative_path = \"{}\"\ndate_source = \"{}\"\n",
+            relative_path, date_source
+        );
+        assert!(matches!(
+            validate_presets(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+
+    let general = "[general]\nmode = \"relative\"\ndate_source = \"accessed\"\n";
+    assert!(matches!(
+        validate_general(&parse_toml(general)),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+}
+
+#[test]
+fn presets_mime_types() {
+    // a preset can match by MIME type alone
+    let valid = r#"
+        [general]
+        mode = "relative"
+        detect_content = true
+
+        [preset.images]
+        enabled = true
+        mime = ["image/*", "application/pdf"]
+        relative_path = "images"
+        absolute_path = "/home/user/images"
+    "#;
+    validate_general(&parse_toml(valid)).unwrap();
+    validate_presets(&parse_toml(valid)).unwrap();
+
+    for invalid in ["[\"image\"]", "[\"*/*\"]", "\"image/*\"", "[\"image/\"]"] {
+        let toml = format!(
+            "[preset.images]\nenabled = true\nrelative_path = \"i\"\nabsolute_path = \"/i\"\nmime = {}\n",
+            invalid
+        );
+        assert!(matches!(
+            validate_presets(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+}
+
+#[test]
+fn presets_exclude_patterns() {
+    let valid = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["txt"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+        exclude = ["draft-*", "regex:^~\\$"]
+    "#;
+    validate_presets(&parse_toml(valid)).unwrap();
+
+    let invalid = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["txt"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+        exclude = ["regex:(draft"]
+    "#;
+    assert!(matches!(
+        validate_presets(&parse_toml(invalid)),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+}
+
 #[test]
 fn presets_empty_table() {
     let toml = r#"[preset.docs]"#;
@@ -157,3 +644,77 @@ fn presets_no_presets_table() {
         Err(ValidationError::MissingSection { section: _ })
     ));
 }
+
+#[test]
+fn exclusions_in_progress_checks() {
+    validate_exclusions(&parse_toml(
+        "[file_exclusions]\nmin_age = 30\nskip_open_files = true\n",
+    ))
+    .unwrap();
+    for invalid in ["min_age = -1", "min_age = \"30s\"", "skip_open_files = 1"] {
+        let toml = format!("[file_exclusions]\n{}\n", invalid);
+        assert!(matches!(
+            validate_exclusions(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+}
+
+// ===== FILE NAMING SECTION TESTS =====
+#[test]
+fn naming_section() {
+    validate_naming(&parse_toml("")).unwrap();
+    validate_naming(&parse_toml(
+        r#"
+        [file_naming]
+        rename_files = true
+        space_replacement = "_"
+        rename_method = "kebab"
+        sanitize_names = false
+        "#,
+    ))
+    .unwrap();
+
+    for invalid in [
+        "rename_files = \"yes\"",
+        "rename_method = \"sponge\"",
+        "space_replacement = \":\"",
+    ] {
+        let toml = format!("[file_naming]\n{}\n", invalid);
+        assert!(matches!(
+            validate_naming(&parse_toml(&toml)),
+            Err(ValidationError::InvalidValue { key: _, value: _ })
+        ));
+    }
+    assert!(matches!(
+        validate_naming(&parse_toml("[file_naming]\nrename = true\n")),
+        Err(ValidationError::InvalidKey { preset: _, key: _ })
+    ));
+}
+
+#[test]
+fn presets_naming_overrides() {
+    let valid = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["txt"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+        rename_files = true
+        rename_method = "snake"
+    "#;
+    validate_presets(&parse_toml(valid)).unwrap();
+
+    let invalid = r#"
+        [preset.docs]
+        enabled = true
+        extension = ["txt"]
+        relative_path = "docs"
+        absolute_path = "/home/user/docs"
+        rename_method = "SNAKE"
+    "#;
+    assert!(matches!(
+        validate_presets(&parse_toml(invalid)),
+        Err(ValidationError::InvalidValue { key: _, value: _ })
+    ));
+}
@@ -0,0 +1,43 @@
+use super::{
+    validate_bool, validate_patterns, validate_size_limits, validate_string_array, ValidationError,
+};
+use toml::Value;
+
+pub fn validate_exclusions(value: &Value) -> Result<(), ValidationError> {
+    // [file_exclusions] is optional
+    let exclusions = match value.get("file_exclusions") {
+        None => return Ok(()),
+        Some(Value::Table(t)) => t,
+        Some(other) => {
+            return Err(ValidationError::InvalidValue {
+                key: "file_exclusions".to_string(),
+                value: other.to_string(),
+            })
+        }
+    };
+
+    for (key, value) in exclusions {
+        match key.as_str() {
+            "patterns" => validate_patterns(key, value)?,
+            "directories" => validate_string_array(key, value)?,
+            "case_sensitive" | "skip_open_files" => validate_bool(key, value)?,
+            "min_age" if value.as_integer().is_none_or(|n| n < 0) => {
+                return Err(ValidationError::InvalidValue {
+                    key: key.clone(),
+                    value: format!("{} (must be a number of seconds)", value),
+                })
+            }
+            "min_age" => {}
+            // checked together below
+            "min_file_size" | "max_file_size" => {}
+            _ => {
+                return Err(ValidationError::InvalidKey {
+                    preset: "file_exclusions".to_string(),
+                    key: key.clone(),
+                })
+            }
+        }
+    }
+
+    validate_size_limits(exclusions)
+}
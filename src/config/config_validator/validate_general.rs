@@ -1,4 +1,9 @@
-use super::ValidationError;
+use super::{
+    validate_bool, validate_conflict_pattern, validate_positive_integer, validate_string_array,
+    ValidationError, CONFLICT_STRATEGIES, DATE_SOURCES, LOG_LEVELS, PROTECTION_SCOPES,
+    SYMLINK_POLICIES, VERIFY_MODES,
+};
+use crate::config::config_processor::process_utils::parse_size;
 use std::collections::HashMap;
 use toml::Value;
 pub fn validate_general(value: &Value) -> Result<(), ValidationError> {
@@ -21,6 +26,22 @@ pub fn validate_general(value: &Value) -> Result<(), ValidationError> {
     );
     allowed_entries.insert("mode", (true, Some(vec!["relative", "absolute"])));
     allowed_entries.insert("presets_path", (false, None));
+    allowed_entries.insert("on_conflict", (false, Some(CONFLICT_STRATEGIES.to_vec())));
+    allowed_entries.insert("conflict_pattern", (false, None));
+    allowed_entries.insert("recursive", (false, None));
+    allowed_entries.insert("max_depth", (false, None));
+    allowed_entries.insert("date_source", (false, Some(DATE_SOURCES.to_vec())));
+    allowed_entries.insert("detect_content", (false, None));
+    allowed_entries.insert("project_markers", (false, None));
+    allowed_entries.insert("protected_paths", (false, None));
+    allowed_entries.insert("log_level", (false, Some(LOG_LEVELS.to_vec())));
+    allowed_entries.insert("log_file", (false, None));
+    allowed_entries.insert("threads", (false, None));
+    allowed_entries.insert("bandwidth_limit", (false, None));
+    allowed_entries.insert("verify", (false, Some(VERIFY_MODES.to_vec())));
+    allowed_entries.insert("preserve_metadata", (false, None));
+    allowed_entries.insert("symlinks", (false, Some(SYMLINK_POLICIES.to_vec())));
+    allowed_entries.insert("include_hidden", (false, None));
 
     // iterate through fields in general
     for (key, value) in general {
@@ -31,16 +52,40 @@ pub fn validate_general(value: &Value) -> Result<(), ValidationError> {
             });
         }
 
+        match key.as_str() {
+            "conflict_pattern" => validate_conflict_pattern(key, value)?,
+            "recursive" | "detect_content" | "log_file" | "preserve_metadata"
+            | "include_hidden" => validate_bool(key, value)?,
+            "project_markers" => validate_string_array(key, value)?,
+            "protected_paths" => validate_protected_paths(key, value)?,
+            "max_depth" | "threads" => validate_positive_integer(key, value)?,
+            "bandwidth_limit" => {
+                parse_size(value).map_err(|e| ValidationError::InvalidValue {
+                    key: key.to_string(),
+                    value: e,
+                })?;
+            }
+            _ => {}
+        }
+
         let (_, valid) = &allowed_entries[key.as_str()];
 
         if let Some(valid) = valid {
-            if let Value::String(s) = value {
-                if !valid.contains(&s.as_str()) {
+            match value {
+                Value::String(s) if valid.contains(&s.as_str()) => {}
+                Value::String(s) => {
                     return Err(ValidationError::InvalidValue {
                         key: key.clone(),
                         value: s.clone(),
                     });
                 }
+                // keys with a fixed set of values only accept strings
+                _ => {
+                    return Err(ValidationError::InvalidValue {
+                        key: key.clone(),
+                        value: value.to_string(),
+                    });
+                }
             }
         }
     }
@@ -55,3 +100,34 @@ pub fn validate_general(value: &Value) -> Result<(), ValidationError> {
     }
     Ok(())
 }
+
+/// protected_paths holds paths, or { path = "...", scope = "exact" | "subtree" } tables
+fn validate_protected_paths(key: &str, value: &Value) -> Result<(), ValidationError> {
+    let invalid = |value: &Value| ValidationError::InvalidValue {
+        key: key.to_string(),
+        value: value.to_string(),
+    };
+    let Value::Array(entries) = value else {
+        return Err(invalid(value));
+    };
+    for entry in entries {
+        match entry {
+            Value::String(path) if !path.trim().is_empty() => {}
+            Value::Table(table) => {
+                let has_path = table
+                    .get("path")
+                    .and_then(Value::as_str)
+                    .is_some_and(|p| !p.trim().is_empty());
+                let valid_scope = table
+                    .get("scope")
+                    .is_none_or(|s| s.as_str().is_some_and(|s| PROTECTION_SCOPES.contains(&s)));
+                let known_keys = table.keys().all(|k| k == "path" || k == "scope");
+                if !(has_path && valid_scope && known_keys) {
+                    return Err(invalid(entry));
+                }
+            }
+            _ => return Err(invalid(entry)),
+        }
+    }
+    Ok(())
+}
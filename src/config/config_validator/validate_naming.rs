@@ -0,0 +1,62 @@
+use super::{validate_bool, ValidationError};
+use crate::config::config_processor::INVALID_CHARS;
+use toml::Value;
+
+/// Accepted values of the `rename_method` key in [file_naming] and in presets
+const RENAME_METHODS: &[&str] = &[
+    "lower", "upper", "title", "camel", "pascal", "snake", "kebab",
+];
+
+/// Keys of [file_naming] that presets can override
+pub(super) const NAMING_KEYS: &[&str] = &[
+    "rename_files",
+    "space_replacement",
+    "rename_method",
+    "sanitize_names",
+];
+
+pub fn validate_naming(value: &Value) -> Result<(), ValidationError> {
+    // [file_naming] is optional
+    let naming = match value.get("file_naming") {
+        None => return Ok(()),
+        Some(Value::Table(t)) => t,
+        Some(other) => {
+            return Err(ValidationError::InvalidValue {
+                key: "file_naming".to_string(),
+                value: other.to_string(),
+            })
+        }
+    };
+
+    for (key, value) in naming {
+        if !NAMING_KEYS.contains(&key.as_str()) {
+            return Err(ValidationError::InvalidKey {
+                preset: "file_naming".to_string(),
+                key: key.clone(),
+            });
+        }
+        validate_naming_key(key, value)?;
+    }
+    Ok(())
+}
+
+/// Validate one of the NAMING_KEYS, in [file_naming] or in a preset
+pub(super) fn validate_naming_key(key: &str, value: &Value) -> Result<(), ValidationError> {
+    let invalid = || ValidationError::InvalidValue {
+        key: key.to_string(),
+        value: value.to_string(),
+    };
+    match key {
+        "rename_files" | "sanitize_names" => validate_bool(key, value),
+        "rename_method" => match value.as_str() {
+            Some(s) if RENAME_METHODS.contains(&s) => Ok(()),
+            _ => Err(invalid()),
+        },
+        // the replacement ends up in file names, so it has to be valid in one
+        "space_replacement" => match value.as_str() {
+            Some(s) if !s.contains(INVALID_CHARS) => Ok(()),
+            _ => Err(invalid()),
+        },
+        _ => Ok(()),
+    }
+}
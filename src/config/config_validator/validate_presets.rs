@@ -1,12 +1,16 @@
 // TODO: if two presets with same extension are enabled, they should have different relative_path and absolute_path
-// TODO: implement a precedence system where the top most preset has the highest precedence
 // TODO: implement a duplicate entry error for duplicate keys in a preset
 // TODO: support for nested presets like [preset.docs.txt] for txt in /documents/txt
 // TODO: implement a duplicate entry error for duplicate extensions in the same preset
-// TODO: A global "*" catch-all extension support to sort files which don't match any presets into a misc folder
-// TODO: support for recursive option which would recursively sort files inside a target directory
-use super::ValidationError;
+use super::validate_naming::{validate_naming_key, NAMING_KEYS};
+use super::{
+    validate_bool, validate_conflict_pattern, validate_dest_path, validate_patterns,
+    validate_positive_integer, validate_size_limits, validate_string_array, ValidationError,
+    CONFLICT_STRATEGIES, DATE_SOURCES,
+};
+use regex::Regex;
 use std::collections::HashMap;
+use std::path::{Component, Path};
 use toml::Value;
 
 pub fn validate_presets(value: &Value) -> Result<(), ValidationError> {
@@ -25,11 +29,59 @@ pub fn validate_presets(value: &Value) -> Result<(), ValidationError> {
         });
     }
 
+    // check if at least one preset is enabled
+    if !validate_preset_tables(presets)? {
+        return Err(ValidationError::NoEnabledPresets);
+    }
+
+    Ok(())
+}
+
+/// Validate a modular preset file from `presets_path`.
+/// Such a file only holds [preset.*] tables and may hold no enabled preset at all.
+pub fn validate_preset_file(value: &Value) -> Result<(), ValidationError> {
+    let Some(table) = value.as_table() else {
+        return Ok(());
+    };
+    for (key, value) in table {
+        match (key.as_str(), value) {
+            ("preset", Value::Table(presets)) => {
+                validate_preset_tables(presets)?;
+            }
+            _ => {
+                return Err(ValidationError::InvalidKey {
+                    preset: "preset file".to_string(),
+                    key: key.clone(),
+                })
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validate every [preset.*] table, returning whether at least one of them is enabled
+fn validate_preset_tables(presets: &toml::Table) -> Result<bool, ValidationError> {
     let mut allowed_entries: HashMap<&str, (bool, Option<Vec<&str>>)> = HashMap::new();
     allowed_entries.insert("enabled", (true, None));
     allowed_entries.insert("extension", (true, None));
     allowed_entries.insert("relative_path", (true, None));
     allowed_entries.insert("absolute_path", (true, None));
+    allowed_entries.insert("on_conflict", (false, None));
+    allowed_entries.insert("conflict_pattern", (false, None));
+    allowed_entries.insert("recursive", (false, None));
+    allowed_entries.insert("max_depth", (false, None));
+    allowed_entries.insert("extensionless", (false, None));
+    allowed_entries.insert("exclude", (false, None));
+    allowed_entries.insert("priority", (false, None));
+    allowed_entries.insert("rule", (false, None));
+    allowed_entries.insert("min_file_size", (false, None));
+    allowed_entries.insert("max_file_size", (false, None));
+    allowed_entries.insert("date_source", (false, None));
+    allowed_entries.insert("mime", (false, None));
+    allowed_entries.insert("include_hidden", (false, None));
+    for key in NAMING_KEYS {
+        allowed_entries.insert(key, (false, None));
+    }
 
     let mut has_enabled = false;
 
@@ -94,22 +146,51 @@ pub fn validate_presets(value: &Value) -> Result<(), ValidationError> {
                         });
                     }
                 }
-                "relative_path" | "absolute_path" => {
-                    if !value.is_str() {
-                        return Err(ValidationError::InvalidValue {
-                            key: key.clone(),
-                            value: value.to_string(),
-                        });
-                    }
+                "on_conflict"
+                    if !value
+                        .as_str()
+                        .is_some_and(|s| CONFLICT_STRATEGIES.contains(&s)) =>
+                {
+                    return Err(ValidationError::InvalidValue {
+                        key: key.clone(),
+                        value: value.to_string(),
+                    });
+                }
+                "conflict_pattern" => validate_conflict_pattern(key, value)?,
+                "recursive" | "extensionless" | "include_hidden" => validate_bool(key, value)?,
+                "max_depth" => validate_positive_integer(key, value)?,
+                "exclude" => validate_patterns(key, value)?,
+                "mime" => validate_mime_types(key, value)?,
+                "rule" => validate_rules(preset_name, value)?,
+                "priority" if !value.is_integer() => {
+                    return Err(ValidationError::InvalidValue {
+                        key: key.clone(),
+                        value: value.to_string(),
+                    });
+                }
+                "date_source" if !value.as_str().is_some_and(|s| DATE_SOURCES.contains(&s)) => {
+                    return Err(ValidationError::InvalidValue {
+                        key: key.clone(),
+                        value: value.to_string(),
+                    });
                 }
+                "relative_path" | "absolute_path" => validate_dest_path(key, value)?,
+                key if NAMING_KEYS.contains(&key) => validate_naming_key(key, value)?,
                 _ => {}
             }
         }
 
+        validate_size_limits(preset_table)?;
+
         // check for missing required keys
         for (allowed_key, (required, _)) in &allowed_entries {
-            // Special case: "dirs" preset does not require "extension"
-            if *allowed_key == "extension" && preset_name == "dirs" {
+            // Special case: "dirs", extensionless-only and MIME-only presets do not require "extension"
+            let extensionless = preset_table
+                .get("extensionless")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let by_mime = preset_table.contains_key("mime");
+            if *allowed_key == "extension" && (preset_name == "dirs" || extensionless || by_mime) {
                 continue;
             }
 
@@ -122,10 +203,100 @@ pub fn validate_presets(value: &Value) -> Result<(), ValidationError> {
         }
     }
 
-    // check if at least one preset is enabled
-    if !has_enabled {
-        return Err(ValidationError::NoEnabledPresets);
+    Ok(has_enabled)
+}
+
+/// Validate the [[preset.X.rule]] blocks of a preset
+fn validate_rules(preset_name: &str, value: &Value) -> Result<(), ValidationError> {
+    let section = format!("[[preset.{}.rule]]", preset_name);
+    let invalid = |key: &str, value: String| ValidationError::InvalidValue {
+        key: key.to_string(),
+        value,
+    };
+    let rules = value
+        .as_array()
+        .ok_or_else(|| invalid("rule", "must be an array of tables".to_string()))?;
+
+    for rule in rules {
+        let rule = rule
+            .as_table()
+            .ok_or_else(|| invalid("rule", "must be an array of tables".to_string()))?;
+
+        for (key, value) in rule {
+            match key.as_str() {
+                "extension" => validate_string_array(key, value)?,
+                "filename_contains" if !value.is_str() => {
+                    return Err(invalid(key, value.to_string()))
+                }
+                "filename_contains" => {}
+                "filename_regex" => {
+                    let expr = value
+                        .as_str()
+                        .ok_or_else(|| invalid(key, value.to_string()))?;
+                    Regex::new(expr).map_err(|e| invalid(key, format!("{} ({})", expr, e)))?;
+                }
+                "match_case" => validate_bool(key, value)?,
+                "modified_within_days" => validate_positive_integer(key, value)?,
+                // checked together below
+                "min_file_size" | "max_file_size" => {}
+                "folder" => {
+                    // the folder has to stay inside the preset destination
+                    let folder = value
+                        .as_str()
+                        .filter(|s| !s.is_empty())
+                        .ok_or_else(|| invalid(key, value.to_string()))?;
+                    let inside = Path::new(folder)
+                        .components()
+                        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+                    if !inside {
+                        return Err(invalid(
+                            key,
+                            format!("{} (must be relative to the preset folder)", folder),
+                        ));
+                    }
+                }
+                _ => {
+                    return Err(ValidationError::InvalidKey {
+                        preset: section.clone(),
+                        key: key.clone(),
+                    })
+                }
+            }
+        }
+
+        validate_size_limits(rule)?;
+
+        if !rule.contains_key("folder") {
+            return Err(ValidationError::MissingKey {
+                key: "folder".to_string(),
+                section: section.clone(),
+            });
+        }
     }
+    Ok(())
+}
 
+/// Validate a `mime` array: "type/subtype" or "type/*" strings
+fn validate_mime_types(key: &str, value: &Value) -> Result<(), ValidationError> {
+    validate_string_array(key, value)?;
+    for mime in value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+    {
+        let valid = mime
+            .split_once('/')
+            .is_some_and(|(kind, subtype)| !kind.is_empty() && !subtype.is_empty() && kind != "*");
+        if !valid {
+            return Err(ValidationError::InvalidValue {
+                key: key.to_string(),
+                value: format!(
+                    "{} (expected a MIME type like \"image/png\" or \"image/*\")",
+                    mime
+                ),
+            });
+        }
+    }
     Ok(())
 }
@@ -1,6 +1,7 @@
 pub mod config_edit;
 pub mod config_init;
 pub mod config_parser;
+pub mod config_pattern;
 pub mod config_processor;
 pub mod config_reset;
 pub mod config_show;
@@ -0,0 +1,395 @@
+// This module lists and exports past sort runs recorded in the journal directory
+use chrono::{DateTime, Local, NaiveDate};
+use colored::Colorize;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::core::journal::{self, JournalMove, MoveOutcome, RunRecord};
+use crate::core::sort::sort::{group_by_dest_dir, MoveMethod, PlannedMove};
+
+/// Filters applied to `iris history` and `iris history show`
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub preset: Option<String>,
+    pub path: Option<String>,
+}
+
+/// Overview of a single run, as listed by `iris history`
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub timestamp: DateTime<Local>,
+    pub target: PathBuf,
+    pub moved: usize,
+    pub failed: usize,
+    // moves that were planned but never confirmed (interrupted run)
+    pub pending: usize,
+    pub undone: Option<DateTime<Local>>,
+}
+
+/// A single move of a run, as listed by `iris history show`
+#[derive(Debug, Serialize)]
+pub struct MoveDetail {
+    pub id: usize,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub preset: String,
+    pub status: &'static str,
+    pub method: Option<MoveMethod>,
+    pub error: Option<String>,
+}
+
+/// Full details of a run, as exported by `iris history show --json`
+#[derive(Debug, Serialize)]
+pub struct RunDetail {
+    #[serde(flatten)]
+    pub summary: RunSummary,
+    pub moves: Vec<MoveDetail>,
+}
+
+impl HistoryFilter {
+    /// Check the run date against the since/until bounds (both inclusive)
+    fn matches_date(&self, run: &RunRecord) -> bool {
+        let date = run.header.timestamp.date_naive();
+        let after_since = self.since.is_none_or(|since| date >= since);
+        let before_until = self.until.is_none_or(|until| date <= until);
+        after_since && before_until
+    }
+
+    /// Check a single move against the preset and path filters
+    fn matches_move(&self, run: &RunRecord, journal_move: &JournalMove) -> bool {
+        let entry = &journal_move.entry;
+        if let Some(preset) = &self.preset {
+            if !entry.preset.eq_ignore_ascii_case(preset) {
+                return false;
+            }
+        }
+        if let Some(needle) = &self.path {
+            let contains = |p: &Path| p.to_string_lossy().contains(needle.as_str());
+            if !contains(&entry.source)
+                && !contains(&entry.destination)
+                && !contains(&run.header.target)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// A run matches if its date is in range and at least one of its moves matches
+    fn matches_run(&self, run: &RunRecord) -> bool {
+        if !self.matches_date(run) {
+            return false;
+        }
+        if self.preset.is_none() && self.path.is_none() {
+            return true;
+        }
+        run.moves.iter().any(|m| self.matches_move(run, m))
+    }
+}
+
+/// Summarize a run, counting only the moves selected by the filter
+fn summarize(run: &RunRecord, moves: &[&JournalMove]) -> RunSummary {
+    let count = |pred: fn(&MoveOutcome) -> bool| moves.iter().filter(|m| pred(&m.outcome)).count();
+    RunSummary {
+        run_id: run.header.run_id.clone(),
+        timestamp: run.header.timestamp,
+        target: run.header.target.clone(),
+        moved: count(|o| matches!(o, MoveOutcome::Completed(_))),
+        failed: count(|o| matches!(o, MoveOutcome::Failed(_))),
+        pending: count(|o| matches!(o, MoveOutcome::Pending)),
+        undone: run.undone,
+    }
+}
+
+/// Load the runs and the moves within them that match the filter
+fn filtered_runs(
+    journal_dir: &Path,
+    filter: &HistoryFilter,
+) -> Result<Vec<RunSummary>, Box<dyn std::error::Error>> {
+    let runs = journal::list_runs(journal_dir)?;
+    Ok(runs
+        .iter()
+        .filter(|run| filter.matches_run(run))
+        .map(|run| {
+            let moves: Vec<&JournalMove> = run
+                .moves
+                .iter()
+                .filter(|m| filter.matches_move(run, m))
+                .collect();
+            summarize(run, &moves)
+        })
+        .collect())
+}
+
+/// List past sort runs, newest first
+pub fn list_history(
+    journal_dir: &Path,
+    filter: &HistoryFilter,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut summaries = filtered_runs(journal_dir, filter)?;
+    summaries.reverse();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+        return Ok(());
+    }
+
+    if summaries.is_empty() {
+        println!("{}", "No sort runs found.".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        format!(
+            "{:<20} {:<20} {:>6} {:>7}  {}",
+            "RUN ID", "DATE", "FILES", "FAILED", "TARGET"
+        )
+        .bold()
+    );
+    for summary in &summaries {
+        let line = format!(
+            "{:<20} {:<20} {:>6} {:>7}  {}",
+            summary.run_id,
+            summary.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            summary.moved,
+            summary.failed,
+            summary.target.display()
+        );
+        if summary.undone.is_some() {
+            println!("{} {}", line.dimmed(), "(undone)".dimmed());
+        } else if summary.failed > 0 || summary.pending > 0 {
+            println!("{}", line.yellow());
+        } else {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Show every move of a single run, grouped by destination directory
+pub fn show_run(
+    journal_dir: &Path,
+    run_id: &str,
+    filter: &HistoryFilter,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = journal::run_file_path(journal_dir, run_id)?;
+    if !path.exists() {
+        return Err(format!("no sort run with id '{}' was found", run_id).into());
+    }
+    let run = journal::load_run(&path)?;
+    let moves: Vec<&JournalMove> = run
+        .moves
+        .iter()
+        .filter(|m| filter.matches_move(&run, m))
+        .collect();
+
+    if json {
+        let detail = RunDetail {
+            summary: summarize(&run, &moves),
+            moves: moves.iter().map(|m| move_detail(m)).collect(),
+        };
+        println!("{}", serde_json::to_string_pretty(&detail)?);
+        return Ok(());
+    }
+
+    println!(
+        "Run {} ({}) in: {}",
+        run.header.run_id,
+        run.header.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        run.header.target.display()
+    );
+    if let Some(when) = run.undone {
+        println!(
+            "{}",
+            format!("Undone on {}", when.format("%Y-%m-%d %H:%M:%S")).yellow()
+        );
+    }
+
+    // reuse the grouping of the sort output so both read the same way
+    let planned: Vec<PlannedMove> = moves
+        .iter()
+        .map(|m| PlannedMove {
+            source: m.entry.source.clone(),
+            destination: m.entry.destination.clone(),
+            preset: m.entry.preset.clone(),
+            ..Default::default()
+        })
+        .collect();
+
+    for (dest_dir, group) in group_by_dest_dir(&planned) {
+        println!("{}", format!("  → {}", dest_dir.display()).bright_cyan());
+        for planned_move in group {
+            let outcome = moves
+                .iter()
+                .find(|m| m.entry.destination == planned_move.destination)
+                .map(|m| &m.outcome);
+            let name = planned_move
+                .source
+                .file_name()
+                .unwrap_or(planned_move.source.as_os_str())
+                .to_string_lossy();
+            match outcome {
+                Some(MoveOutcome::Failed(e)) => {
+                    println!("{}", format!("    {} (failed: {})", name, e).red())
+                }
+                Some(MoveOutcome::Pending) => {
+                    println!("{}", format!("    {} (not confirmed)", name).yellow())
+                }
+                _ => println!("{}", format!("    {}", name).white()),
+            }
+        }
+        println!();
+    }
+
+    let summary = summarize(&run, &moves);
+    println!(
+        "{}",
+        format!(
+            "Summary: {} moved, {} failed, {} not confirmed",
+            summary.moved, summary.failed, summary.pending
+        )
+        .green()
+    );
+    Ok(())
+}
+
+/// Convert a journal move into its exported form
+fn move_detail(journal_move: &JournalMove) -> MoveDetail {
+    let (status, method, error) = match &journal_move.outcome {
+        MoveOutcome::Completed(method) => ("moved", Some(*method), None),
+        MoveOutcome::Failed(e) => ("failed", None, Some(e.clone())),
+        MoveOutcome::Pending => ("pending", None, None),
+    };
+    MoveDetail {
+        id: journal_move.entry.id,
+        source: journal_move.entry.source.clone(),
+        destination: journal_move.entry.destination.clone(),
+        preset: journal_move.entry.preset.clone(),
+        status,
+        method,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::journal::{JournalRecord, JournalWriter, MoveEntry};
+    use tempfile::tempdir;
+
+    fn write_run(journal_dir: &Path, target: &str, moves: &[(&str, &str, &str)]) -> String {
+        let journal = JournalWriter::create(journal_dir, Path::new(target)).unwrap();
+        for (id, (source, destination, preset)) in moves.iter().enumerate() {
+            journal
+                .record(&JournalRecord::Planned(MoveEntry {
+                    id,
+                    source: PathBuf::from(source),
+                    destination: PathBuf::from(destination),
+                    preset: preset.to_string(),
+                    is_dir: false,
+                    size: 0,
+                    modified: None,
+                    link_target: None,
+                }))
+                .unwrap();
+            journal
+                .record(&JournalRecord::Completed {
+                    id,
+                    method: MoveMethod::Rename,
+                })
+                .unwrap();
+        }
+        journal.run_id().to_string()
+    }
+
+    #[test]
+    fn test_filter_by_preset_and_path() {
+        let journal_dir = tempdir().unwrap();
+        write_run(
+            journal_dir.path(),
+            "/home/user/Downloads",
+            &[
+                (
+                    "/home/user/Downloads/a.pdf",
+                    "/home/user/Downloads/documents/a.pdf",
+                    "documents",
+                ),
+                (
+                    "/home/user/Downloads/b.png",
+                    "/home/user/Downloads/images/b.png",
+                    "images",
+                ),
+            ],
+        );
+        write_run(
+            journal_dir.path(),
+            "/home/user/Desktop",
+            &[(
+                "/home/user/Desktop/c.png",
+                "/home/user/Desktop/images/c.png",
+                "images",
+            )],
+        );
+
+        let all = filtered_runs(journal_dir.path(), &HistoryFilter::default()).unwrap();
+        assert_eq!(all.len(), 2);
+
+        let documents = HistoryFilter {
+            preset: Some("documents".to_string()),
+            ..Default::default()
+        };
+        let runs = filtered_runs(journal_dir.path(), &documents).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].moved, 1);
+
+        let desktop = HistoryFilter {
+            path: Some("Desktop".to_string()),
+            ..Default::default()
+        };
+        let runs = filtered_runs(journal_dir.path(), &desktop).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].target, PathBuf::from("/home/user/Desktop"));
+    }
+
+    #[test]
+    fn test_filter_by_date() {
+        let journal_dir = tempdir().unwrap();
+        write_run(
+            journal_dir.path(),
+            "/t",
+            &[("/t/a.txt", "/t/docs/a.txt", "docs")],
+        );
+
+        let today = Local::now().date_naive();
+        let in_range = HistoryFilter {
+            since: Some(today),
+            until: Some(today),
+            ..Default::default()
+        };
+        assert_eq!(
+            filtered_runs(journal_dir.path(), &in_range).unwrap().len(),
+            1
+        );
+
+        let future = HistoryFilter {
+            since: today.succ_opt(),
+            ..Default::default()
+        };
+        assert!(filtered_runs(journal_dir.path(), &future)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_show_unknown_run() {
+        let journal_dir = tempdir().unwrap();
+        let result = show_run(journal_dir.path(), "nope", &HistoryFilter::default(), true);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use super::{JournalMove, JournalRecord, MoveOutcome, RunRecord, JOURNAL_EXTENSION};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Path of the journal file for the given run id. The id comes from the command line,
+/// so ids that could point outside the journal directory are rejected.
+pub fn run_file_path(dir: &Path, run_id: &str) -> io::Result<PathBuf> {
+    if run_id.is_empty() || run_id.contains(['/', '\\']) || run_id.contains("..") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid run id '{}'", run_id),
+        ));
+    }
+    Ok(dir.join(format!("{}.{}", run_id, JOURNAL_EXTENSION)))
+}
+
+/// Load and reconstruct a run from its journal file
+pub fn load_run(path: &Path) -> io::Result<RunRecord> {
+    let content = fs::read_to_string(path)?;
+    let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+
+    let mut header = None;
+    let mut moves: Vec<JournalMove> = Vec::new();
+    let mut created_dirs = Vec::new();
+    let mut undone = None;
+    let mut partially_undone = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let record: JournalRecord = match serde_json::from_str(line) {
+            Ok(r) => r,
+            // a torn last line means iris was interrupted while writing it
+            Err(_) if index + 1 == lines.len() => break,
+            Err(e) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("corrupt journal '{}': {}", path.display(), e),
+                ))
+            }
+        };
+
+        match record {
+            JournalRecord::Run(h) => header = Some(h),
+            JournalRecord::Planned(entry) => moves.push(JournalMove {
+                entry,
+                outcome: MoveOutcome::Pending,
+                backup: None,
+                restored: false,
+            }),
+            JournalRecord::DirCreated { path } => created_dirs.push(path),
+            JournalRecord::Backup { id, path } => {
+                if let Some(m) = moves.iter_mut().find(|m| m.entry.id == id) {
+                    m.backup = Some(path);
+                }
+            }
+            JournalRecord::Completed { id, method } => {
+                if let Some(m) = moves.iter_mut().find(|m| m.entry.id == id) {
+                    m.outcome = MoveOutcome::Completed(method);
+                }
+            }
+            JournalRecord::Failed { id, error } => {
+                if let Some(m) = moves.iter_mut().find(|m| m.entry.id == id) {
+                    m.outcome = MoveOutcome::Failed(error);
+                }
+            }
+            JournalRecord::Restored { id } => {
+                if let Some(m) = moves.iter_mut().find(|m| m.entry.id == id) {
+                    m.restored = true;
+                }
+            }
+            JournalRecord::Undone { timestamp } => undone = Some(timestamp),
+            JournalRecord::PartiallyUndone { timestamp } => partially_undone = Some(timestamp),
+        }
+    }
+
+    let header = header.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("journal '{}' has no run header", path.display()),
+        )
+    })?;
+
+    Ok(RunRecord {
+        header,
+        moves,
+        created_dirs,
+        undone,
+        partially_undone,
+    })
+}
+
+/// Load every run in the journal directory, oldest first
+pub fn list_runs(dir: &Path) -> io::Result<Vec<RunRecord>> {
+    let mut runs = Vec::new();
+    if !dir.exists() {
+        return Ok(runs);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(JOURNAL_EXTENSION) {
+            continue;
+        }
+        // one unreadable journal should not hide every other run
+        match load_run(&path) {
+            Ok(run) => runs.push(run),
+            Err(e) => log::warn!("skipping journal '{}': {}", path.display(), e),
+        }
+    }
+
+    runs.sort_by(|a, b| {
+        a.header
+            .timestamp
+            .cmp(&b.header.timestamp)
+            .then_with(|| a.header.run_id.cmp(&b.header.run_id))
+    });
+    Ok(runs)
+}
+
+/// Find the most recent run that has not been undone yet and has something to restore.
+/// Runs a previous undo could only partly revert are left to be retried by their id.
+pub fn latest_undoable_run(dir: &Path) -> io::Result<Option<RunRecord>> {
+    Ok(list_runs(dir)?
+        .into_iter()
+        .rev()
+        .find(|r| r.undone.is_none() && r.partially_undone.is_none() && r.has_moves_to_restore()))
+}
@@ -0,0 +1,77 @@
+use super::{JournalRecord, RunHeader, JOURNAL_EXTENSION};
+use chrono::Local;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Append-only writer for the journal of a single sort run.
+/// Safe to share between the rayon workers executing the moves.
+pub struct JournalWriter {
+    run_id: String,
+    file: Mutex<File>,
+}
+
+impl JournalWriter {
+    /// Create a new journal file in `dir` and write the run header
+    pub fn create(dir: &Path, target: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let timestamp = Local::now();
+        let base_id = timestamp.format("%Y%m%d-%H%M%S").to_string();
+
+        // two runs within the same second get a numeric suffix
+        let mut run_id = base_id.clone();
+        let mut counter = 1;
+        let file = loop {
+            let path = dir.join(format!("{}.{}", run_id, JOURNAL_EXTENSION));
+            match OpenOptions::new().append(true).create_new(true).open(&path) {
+                Ok(file) => break file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    run_id = format!("{}-{}", base_id, counter);
+                    counter += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        let writer = JournalWriter {
+            run_id: run_id.clone(),
+            file: Mutex::new(file),
+        };
+        writer.record(&JournalRecord::Run(RunHeader {
+            run_id,
+            timestamp,
+            target: target.to_path_buf(),
+        }))?;
+        Ok(writer)
+    }
+
+    /// Id of the run this journal belongs to
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Append a single record as one JSON line
+    pub fn record(&self, record: &JournalRecord) -> io::Result<()> {
+        let mut line = serde_json::to_string(record).map_err(io::Error::other)?;
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(line.as_bytes())
+    }
+
+    /// Flush the journal to disk, used before executing the planned moves
+    pub fn sync(&self) -> io::Result<()> {
+        let file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.sync_data()
+    }
+}
+
+/// Append a single record to an existing journal file
+pub fn append_record(path: &Path, record: &JournalRecord) -> io::Result<()> {
+    let mut line = serde_json::to_string(record).map_err(io::Error::other)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    file.sync_data()
+}
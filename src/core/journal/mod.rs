@@ -0,0 +1,103 @@
+// This module keeps a persistent record of every sort run under the config directory.
+// Each run gets its own JSON Lines file so that `iris undo` can replay it in reverse.
+// Moves are written as "planned" before they are executed, which means a run that
+// crashed half-way through can still be undone.
+mod journal_reader;
+mod journal_writer;
+
+pub use journal_reader::{latest_undoable_run, list_runs, load_run, run_file_path};
+pub use journal_writer::{append_record, JournalWriter};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::core::sort::sort::MoveMethod;
+
+/// File extension used for journal files inside the journal directory
+const JOURNAL_EXTENSION: &str = "jsonl";
+
+/// Information about a sort run, written as the first record of its journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunHeader {
+    pub run_id: String,
+    pub timestamp: DateTime<Local>,
+    pub target: PathBuf,
+}
+
+/// A single planned move, recorded before it is executed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveEntry {
+    pub id: usize,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub preset: String,
+    pub is_dir: bool,
+    // size and modification time (unix seconds) of the source at planning time.
+    // used by undo to detect destinations that changed after the sort
+    pub size: u64,
+    pub modified: Option<i64>,
+    // what a followed link pointed to, undo puts the link back instead of its copy.
+    // size, time and is_dir then describe that target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<PathBuf>,
+}
+
+/// One line of a journal file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum JournalRecord {
+    Run(RunHeader),
+    Planned(MoveEntry),
+    DirCreated { path: PathBuf },
+    // the file a move is about to overwrite, renamed out of the way so undo can put it back
+    Backup { id: usize, path: PathBuf },
+    Completed { id: usize, method: MoveMethod },
+    Failed { id: usize, error: String },
+    // written by undo for every move it reverted, so a partial undo can be retried
+    Restored { id: usize },
+    Undone { timestamp: DateTime<Local> },
+    // written by an undo that had to leave some entries in place. `iris undo` without a
+    // run id moves on to older runs, the run id retries this one
+    PartiallyUndone { timestamp: DateTime<Local> },
+}
+
+/// What is known about the outcome of a planned move
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveOutcome {
+    // planned but never confirmed (e.g. iris crashed mid-run)
+    Pending,
+    Completed(MoveMethod),
+    Failed(String),
+}
+
+/// A planned move together with its outcome
+#[derive(Debug, Clone)]
+pub struct JournalMove {
+    pub entry: MoveEntry,
+    pub outcome: MoveOutcome,
+    // where the file replaced by an overwrite was kept
+    pub backup: Option<PathBuf>,
+    // already reverted by an earlier undo of the run
+    pub restored: bool,
+}
+
+/// A sort run reconstructed from its journal file
+#[derive(Debug, Clone)]
+pub struct RunRecord {
+    pub header: RunHeader,
+    pub moves: Vec<JournalMove>,
+    pub created_dirs: Vec<PathBuf>,
+    pub undone: Option<DateTime<Local>>,
+    pub partially_undone: Option<DateTime<Local>>,
+}
+
+impl RunRecord {
+    /// Check whether an undo of the run could still restore something: moves that failed
+    /// or were already restored are left out
+    pub fn has_moves_to_restore(&self) -> bool {
+        self.moves
+            .iter()
+            .any(|m| !m.restored && !matches!(m.outcome, MoveOutcome::Failed(_)))
+    }
+}
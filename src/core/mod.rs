@@ -1,2 +1,7 @@
+pub(crate) mod history;
+pub mod journal;
+pub(crate) mod presets;
 pub mod resolver;
 pub(crate) mod sort;
+pub(crate) mod undo;
+pub(crate) mod watch;
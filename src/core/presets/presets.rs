@@ -0,0 +1,155 @@
+// This module reports how the configured presets share extensions
+use colored::Colorize;
+
+use crate::config::config_processor::{IrisConfig, PresetConfig};
+
+/// An extension claimed by more than one enabled preset
+#[derive(Debug)]
+pub struct ExtensionConflict<'a> {
+    pub extension: String,
+    // the preset the sort uses for this extension
+    pub winner: &'a PresetConfig,
+    // the other presets claiming it, in precedence order
+    pub others: Vec<&'a PresetConfig>,
+}
+
+/// Find every extension claimed by more than one enabled preset.
+/// Presets are already in precedence order, so the first claim wins like in the sort
+/// (unless the file is outside its size limits, then the next one is tried).
+pub fn extension_conflicts(config: &IrisConfig) -> Vec<ExtensionConflict<'_>> {
+    config
+        .extension_claims()
+        .into_iter()
+        .filter(|(_, presets)| presets.len() > 1)
+        .map(|(extension, presets)| ExtensionConflict {
+            extension,
+            winner: presets[0],
+            others: presets[1..].to_vec(),
+        })
+        .collect()
+}
+
+/// Print the extension conflicts for `iris presets conflicts`
+pub fn show_conflicts(config: &IrisConfig) {
+    let conflicts = extension_conflicts(config);
+    if conflicts.is_empty() {
+        println!(
+            "{}",
+            "No extension is claimed by more than one enabled preset.".green()
+        );
+        return;
+    }
+
+    for conflict in &conflicts {
+        println!("{}", format!("  .{}", conflict.extension).bright_cyan());
+        println!(
+            "{}",
+            format!(
+                "    {} (wins, {})",
+                conflict.winner.name,
+                details(conflict.winner)
+            )
+            .white()
+        );
+        for preset in &conflict.others {
+            println!(
+                "{}",
+                format!("    {} ({})", preset.name, details(preset)).dimmed()
+            );
+        }
+    }
+    println!();
+    println!(
+        "{}",
+        format!(
+            "Summary: {} extension{} claimed by more than one preset. \
+             Set `priority` on a preset to change the winner.",
+            conflicts.len(),
+            if conflicts.len() == 1 { "" } else { "s" }
+        )
+        .yellow()
+    );
+}
+
+/// Priority and size limits of a preset, which decide who gets a shared extension
+fn details(preset: &PresetConfig) -> String {
+    let mut details = format!("priority {}", preset.priority);
+    if let Some(min) = preset.size.min {
+        details.push_str(&format!(", files of at least {} bytes", min));
+    }
+    if let Some(max) = preset.size.max {
+        details.push_str(&format!(", files of at most {} bytes", max));
+    }
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(name: &str, extension: &[&str], priority: i64) -> PresetConfig {
+        PresetConfig {
+            name: name.to_string(),
+            enabled: true,
+            extension: extension.iter().map(|e| e.to_string()).collect(),
+            priority,
+            ..Default::default()
+        }
+    }
+
+    fn config(presets: Vec<PresetConfig>, order: &[&str]) -> IrisConfig {
+        // reuse the ordering applied when the config is loaded
+        let mut value = toml::Table::new();
+        let mut table = toml::Table::new();
+        for p in &presets {
+            let mut entry = toml::Table::new();
+            entry.insert("enabled".into(), toml::Value::Boolean(p.enabled));
+            entry.insert(
+                "extension".into(),
+                toml::Value::Array(
+                    p.extension
+                        .iter()
+                        .map(|e| toml::Value::String(e.clone()))
+                        .collect(),
+                ),
+            );
+            entry.insert("priority".into(), toml::Value::Integer(p.priority));
+            table.insert(p.name.clone(), toml::Value::Table(entry));
+        }
+        let mut general = toml::Table::new();
+        general.insert("mode".into(), toml::Value::String("relative".into()));
+        value.insert("general".into(), toml::Value::Table(general));
+        value.insert("preset".into(), toml::Value::Table(table));
+        let order: Vec<String> = order.iter().map(|s| s.to_string()).collect();
+        IrisConfig::from_value(&toml::Value::Table(value), &order).unwrap()
+    }
+
+    #[test]
+    fn test_file_order_breaks_ties() {
+        let config = config(
+            vec![
+                preset("documents_pdf", &["pdf"], 0),
+                preset("documents", &["pdf", "txt"], 0),
+            ],
+            &["documents_pdf", "documents"],
+        );
+        let conflicts = extension_conflicts(&config);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].extension, "pdf");
+        assert_eq!(conflicts[0].winner.name, "documents_pdf");
+    }
+
+    #[test]
+    fn test_priority_wins_over_file_order() {
+        let config = config(
+            vec![
+                preset("documents", &["pdf", "PDF"], 0),
+                preset("documents_pdf", &["pdf"], 5),
+            ],
+            &["documents", "documents_pdf"],
+        );
+        let conflicts = extension_conflicts(&config);
+        assert_eq!(conflicts[0].winner.name, "documents_pdf");
+        assert_eq!(conflicts[0].others.len(), 1);
+    }
+}
@@ -1,10 +1,34 @@
 // This module is responsible for resolving the destination base path for a given preset and mode
 // This should be called in an iterator over the enabled presets
-use crate::config::config_processor::{Mode, PresetConfig};
+use crate::config::config_processor::{DateSource, Mode, PresetConfig};
+use crate::core::resolver::dest_template::{self, FolderMatcher};
 use std::path::{Path, PathBuf};
 
-#[allow(dead_code)]
-pub fn get_dest_base(target: &Path, preset: &PresetConfig, mode: Mode) -> Result<PathBuf, String> {
+/// Folders the preset sorts into: the configured path up to its first placeholder, and
+/// every folder its placeholders expand to
+pub fn get_dest_folders(
+    target: &Path,
+    preset: &PresetConfig,
+    mode: Mode,
+) -> Result<FolderMatcher, String> {
+    get_dest_template(target, preset, mode)
+        .map(|template| FolderMatcher::new(&template, &preset.name))
+}
+
+/// Destination base of a single entry, with the placeholders of the path expanded
+pub fn get_dest_for_entry(
+    target: &Path,
+    preset: &PresetConfig,
+    mode: Mode,
+    entry: &Path,
+    date_source: DateSource,
+) -> Result<PathBuf, String> {
+    get_dest_template(target, preset, mode)
+        .map(|template| dest_template::expand(&template, entry, &preset.name, date_source))
+}
+
+/// The configured destination for the mode, placeholders included
+fn get_dest_template(target: &Path, preset: &PresetConfig, mode: Mode) -> Result<PathBuf, String> {
     match mode {
         Mode::Relative => {
             let rel = preset
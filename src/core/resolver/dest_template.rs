@@ -0,0 +1,304 @@
+// This module expands the placeholders of a destination template (e.g. "photos/{year}/{month}")
+// for a single entry
+use chrono::{DateTime, Datelike, Local};
+use regex::Regex;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::config_processor::{DateSource, PLACEHOLDERS};
+
+/// Used for `{ext}` when the entry has no extension
+const NO_EXTENSION: &str = "no_extension";
+
+/// Upper bounds (exclusive) of the `{size_bucket}` values, the last bucket is "huge"
+const SIZE_BUCKETS: &[(u64, &str)] = &[
+    (1_000_000, "small"),
+    (100_000_000, "medium"),
+    (1_000_000_000, "large"),
+];
+
+/// `{size_bucket}` of entries above every limit of SIZE_BUCKETS
+const LARGEST_SIZE_BUCKET: &str = "huge";
+
+/// Check whether a path contains placeholders
+pub fn is_template(path: &Path) -> bool {
+    path.to_string_lossy().contains('{')
+}
+
+/// The part of a template before its first placeholder, which is the same for every entry
+pub fn static_prefix(template: &Path) -> PathBuf {
+    template
+        .components()
+        .take_while(|c| !c.as_os_str().to_string_lossy().contains('{'))
+        .map(Component::as_os_str)
+        .collect()
+}
+
+/// Matches the folders a template expands to below its static prefix, e.g. "2024" and
+/// "2024/05" for "{year}/{month}"
+#[derive(Debug, Clone)]
+pub struct FolderMatcher {
+    prefix: PathBuf,
+    // one pattern per component of the template after the prefix
+    levels: Vec<Option<Regex>>,
+}
+
+impl FolderMatcher {
+    pub fn new(template: &Path, preset: &str) -> Self {
+        let prefix = static_prefix(template);
+        let levels = template
+            .components()
+            .skip(prefix.components().count())
+            .map(|c| component_regex(&c.as_os_str().to_string_lossy(), preset))
+            .collect();
+        FolderMatcher { prefix, levels }
+    }
+
+    /// Check whether `dir` is the static prefix, or a folder expanded from the template
+    /// at any depth below it
+    pub fn matches(&self, dir: &Path) -> bool {
+        let Ok(rest) = dir.strip_prefix(&self.prefix) else {
+            return false;
+        };
+        let names: Vec<Component> = rest.components().collect();
+        names.len() <= self.levels.len()
+            && names.iter().zip(&self.levels).all(|(name, level)| {
+                level
+                    .as_ref()
+                    .is_some_and(|r| r.is_match(&name.as_os_str().to_string_lossy()))
+            })
+    }
+}
+
+/// Regex matching every value a template component can expand to, e.g. "{year}-{month}"
+fn component_regex(component: &str, preset: &str) -> Option<Regex> {
+    let mut pattern = String::from("^");
+    let mut rest = component;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        pattern.push_str(&regex::escape(&rest[..start]));
+        pattern.push_str(&placeholder_regex(&rest[start + 1..start + len], preset));
+        rest = &rest[start + len + 1..];
+    }
+    pattern.push_str(&regex::escape(rest));
+    pattern.push('$');
+    Regex::new(&pattern).ok()
+}
+
+/// Regex matching the values `expand` gives a placeholder
+fn placeholder_regex(placeholder: &str, preset: &str) -> String {
+    match placeholder {
+        "year" => r"\d{4}".to_string(),
+        "month" | "day" | "week" => r"\d{2}".to_string(),
+        "preset" => regex::escape(preset),
+        "stem_initial" => r"(?:\w+|#)".to_string(),
+        "size_bucket" => {
+            let names: Vec<&str> = SIZE_BUCKETS.iter().map(|(_, name)| *name).collect();
+            format!("(?:{}|{})", names.join("|"), LARGEST_SIZE_BUCKET)
+        }
+        // {ext} can be anything
+        _ => ".+".to_string(),
+    }
+}
+
+/// Expand every placeholder of `template` for the entry at `path`
+pub fn expand(template: &Path, path: &Path, preset: &str, date_source: DateSource) -> PathBuf {
+    if !is_template(template) {
+        return template.to_path_buf();
+    }
+
+    // metadata is only read if a placeholder needs it
+    let needs_metadata = ["year", "month", "day", "week", "size_bucket"]
+        .iter()
+        .any(|p| template_contains(template, &format!("{{{}}}", p)));
+    let metadata = if needs_metadata {
+        fs::metadata(path).ok()
+    } else {
+        None
+    };
+    let date = entry_date(metadata.as_ref(), date_source);
+
+    // each component is expanded on its own, the ones without placeholders are kept as
+    // they are so names that are not valid UTF-8 survive
+    template
+        .components()
+        .map(|component| match component.as_os_str().to_str() {
+            Some(name) if name.contains('{') => OsString::from(expand_component(
+                name,
+                path,
+                preset,
+                date,
+                metadata.as_ref(),
+            )),
+            _ => component.as_os_str().to_os_string(),
+        })
+        .collect()
+}
+
+/// Check whether a component of the template contains `key`
+fn template_contains(template: &Path, key: &str) -> bool {
+    template
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .any(|c| c.contains(key))
+}
+
+/// Replace the placeholders of a single template component
+fn expand_component(
+    component: &str,
+    path: &Path,
+    preset: &str,
+    date: DateTime<Local>,
+    metadata: Option<&fs::Metadata>,
+) -> String {
+    let mut expanded = component.to_string();
+    for placeholder in PLACEHOLDERS {
+        let key = format!("{{{}}}", placeholder);
+        if !expanded.contains(&key) {
+            continue;
+        }
+        let value = match *placeholder {
+            "year" => format!("{:04}", date.year()),
+            "month" => format!("{:02}", date.month()),
+            "day" => format!("{:02}", date.day()),
+            "week" => format!("{:02}", date.iso_week().week()),
+            "ext" => path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| NO_EXTENSION.to_string()),
+            "preset" => preset.to_string(),
+            "stem_initial" => stem_initial(path),
+            "size_bucket" => size_bucket(metadata.map(|m| m.len()).unwrap_or(0)).to_string(),
+            _ => continue,
+        };
+        expanded = expanded.replace(&key, &value);
+    }
+    expanded
+}
+
+/// Date of the entry according to the configured source, falling back to the
+/// modification time and then to now when the filesystem does not provide it
+fn entry_date(metadata: Option<&fs::Metadata>, source: DateSource) -> DateTime<Local> {
+    let time = match (metadata, source) {
+        (_, DateSource::Now) => None,
+        (Some(meta), DateSource::Created) => meta.created().or_else(|_| meta.modified()).ok(),
+        (Some(meta), DateSource::Modified) => meta.modified().ok(),
+        (None, _) => None,
+    };
+    time.map(DateTime::<Local>::from).unwrap_or_else(Local::now)
+}
+
+/// Uppercased first character of the file stem, "#" when it is not alphanumeric
+fn stem_initial(path: &Path) -> String {
+    let initial = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(|s| s.chars().next());
+    match initial {
+        Some(c) if c.is_alphanumeric() => c.to_uppercase().collect(),
+        _ => "#".to_string(),
+    }
+}
+
+fn size_bucket(size: u64) -> &'static str {
+    SIZE_BUCKETS
+        .iter()
+        .find(|(limit, _)| size < *limit)
+        .map(|(_, name)| *name)
+        .unwrap_or(LARGEST_SIZE_BUCKET)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_expand_placeholders() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("invoice.PDF");
+        fs::write(&file, "pdf").unwrap();
+
+        let now = Local::now();
+        let expanded = expand(
+            Path::new("/docs/{preset}/{year}-{month}/{stem_initial}/{ext}/{size_bucket}"),
+            &file,
+            "invoices",
+            DateSource::Modified,
+        );
+        assert_eq!(
+            expanded,
+            PathBuf::from(format!(
+                "/docs/invoices/{}-{:02}/I/pdf/small",
+                now.year(),
+                now.month()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_expand_without_placeholders() {
+        let template = Path::new("documents/pdf");
+        assert_eq!(
+            expand(template, Path::new("a.pdf"), "docs", DateSource::Modified),
+            template
+        );
+        assert_eq!(
+            expand(
+                Path::new("{ext}/{stem_initial}"),
+                Path::new("_notes"),
+                "docs",
+                DateSource::Now
+            ),
+            PathBuf::from("no_extension/#")
+        );
+    }
+
+    #[test]
+    fn test_static_prefix() {
+        assert_eq!(
+            static_prefix(Path::new("/home/me/Pictures/{year}/{month}")),
+            PathBuf::from("/home/me/Pictures")
+        );
+        assert_eq!(static_prefix(Path::new("{year}")), PathBuf::new());
+    }
+
+    #[test]
+    fn test_folder_matcher() {
+        let matcher = FolderMatcher::new(Path::new("/t/{year}/{month}"), "photos");
+        assert!(matcher.matches(Path::new("/t/2024")));
+        assert!(matcher.matches(Path::new("/t/2024/05")));
+        assert!(!matcher.matches(Path::new("/t/inbox")));
+        assert!(!matcher.matches(Path::new("/t/2024/may")));
+        assert!(!matcher.matches(Path::new("/t/2024/05/trip")));
+        assert!(!matcher.matches(Path::new("/other/2024")));
+
+        let matcher = FolderMatcher::new(Path::new("/t/sorted/{preset}-{size_bucket}"), "docs");
+        assert!(matcher.matches(Path::new("/t/sorted")));
+        assert!(matcher.matches(Path::new("/t/sorted/docs-huge")));
+        assert!(!matcher.matches(Path::new("/t/sorted/docs-tiny")));
+        assert!(!matcher.matches(Path::new("/t/sorted/images-small")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expand_keeps_non_utf8_components() {
+        use std::os::unix::ffi::OsStrExt;
+        let raw = std::ffi::OsStr::from_bytes(b"caf\xe9");
+        let template = Path::new("/t").join(raw).join("{ext}");
+        assert_eq!(
+            expand(&template, Path::new("a.pdf"), "docs", DateSource::Now),
+            Path::new("/t").join(raw).join("pdf")
+        );
+    }
+
+    #[test]
+    fn test_size_bucket() {
+        assert_eq!(size_bucket(0), "small");
+        assert_eq!(size_bucket(50_000_000), "medium");
+        assert_eq!(size_bucket(5_000_000_000), "huge");
+    }
+}
@@ -1,2 +1,3 @@
 pub mod dest_base_resolver;
+pub mod dest_template;
 pub mod target_resolver;
@@ -84,9 +84,10 @@ mod tests {
             general: GeneralConfig {
                 target,
                 mode: Mode::Relative,
-                presets_path: None,
+                ..Default::default()
             },
             presets: vec![],
+            ..Default::default()
         }
     }
 
@@ -0,0 +1,360 @@
+// This module decides what happens when the destination of a planned move already exists
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::config_processor::ConflictStrategy;
+use crate::core::sort::sort::hash_file;
+
+/// Pattern used for renamed collisions when none is configured (file-1.txt, file-2.txt, ...)
+pub const DEFAULT_CONFLICT_PATTERN: &str = "{stem}-{n}.{ext}";
+
+/// Outcome of checking a desired destination against existing files
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    // move to this path (renamed with the conflict pattern if needed)
+    Move(PathBuf),
+    // move onto the existing file, replacing it
+    Overwrite(PathBuf),
+    // the existing file has identical content, the source can be removed
+    Dedupe(PathBuf),
+    // leave the source where it is, with the reason why
+    Skip(String),
+}
+
+/// Resolve the final destination of `src` given the configured conflict strategy.
+/// Every destination handed out is recorded in `reserved`, with the source moving there,
+/// so later moves in the same run never target it again.
+pub fn resolve_destination(
+    src: &Path,
+    desired: &Path,
+    strategy: ConflictStrategy,
+    pattern: &str,
+    reserved: &mut HashMap<PathBuf, PathBuf>,
+) -> Resolution {
+    if let Some(other) = reserved.get(desired) {
+        return resolve_collision_in_run(src, other.clone(), desired, strategy, pattern, reserved);
+    }
+
+    let dst_meta = match fs::symlink_metadata(desired) {
+        Ok(meta) => meta,
+        Err(_) => {
+            reserved.insert(desired.to_path_buf(), src.to_path_buf());
+            return Resolution::Move(desired.to_path_buf());
+        }
+    };
+
+    if strategy == ConflictStrategy::Skip {
+        return Resolution::Skip("destination already exists".to_string());
+    }
+
+    // replacing or comparing only makes sense between two files
+    let src_meta = match fs::metadata(src) {
+        Ok(meta) if meta.is_file() && dst_meta.is_file() => meta,
+        _ => return Resolution::Move(reserve_unique_destination(src, desired, pattern, reserved)),
+    };
+
+    let resolution = match strategy {
+        ConflictStrategy::Rename | ConflictStrategy::Skip => {
+            return Resolution::Move(reserve_unique_destination(src, desired, pattern, reserved));
+        }
+        ConflictStrategy::Overwrite => Resolution::Overwrite(desired.to_path_buf()),
+        ConflictStrategy::OverwriteIfNewer => match (src_meta.modified(), dst_meta.modified()) {
+            (Ok(src_time), Ok(dst_time)) if src_time > dst_time => {
+                Resolution::Overwrite(desired.to_path_buf())
+            }
+            _ => Resolution::Skip("existing file is not older".to_string()),
+        },
+        ConflictStrategy::KeepLarger => {
+            if src_meta.len() > dst_meta.len() {
+                Resolution::Overwrite(desired.to_path_buf())
+            } else {
+                Resolution::Skip("existing file is not smaller".to_string())
+            }
+        }
+        ConflictStrategy::Dedupe => {
+            if same_content(src, desired, &src_meta, &dst_meta) {
+                Resolution::Dedupe(desired.to_path_buf())
+            } else {
+                return Resolution::Move(reserve_unique_destination(
+                    src, desired, pattern, reserved,
+                ));
+            }
+        }
+    };
+
+    if !matches!(resolution, Resolution::Skip(_)) {
+        reserved.insert(desired.to_path_buf(), src.to_path_buf());
+    }
+    resolution
+}
+
+/// Resolve a collision with `other`, moved to `desired` earlier in this run. Nothing is
+/// there yet, so `skip` and `dedupe` compare against `other` itself. Overwriting a file
+/// that is still being moved cannot be done safely, those strategies rename instead.
+fn resolve_collision_in_run(
+    src: &Path,
+    other: PathBuf,
+    desired: &Path,
+    strategy: ConflictStrategy,
+    pattern: &str,
+    reserved: &mut HashMap<PathBuf, PathBuf>,
+) -> Resolution {
+    match strategy {
+        ConflictStrategy::Skip => {
+            return Resolution::Skip(format!(
+                "'{}' is moved to the same destination",
+                other.display()
+            ))
+        }
+        ConflictStrategy::Dedupe => {
+            if let (Ok(src_meta), Ok(other_meta)) = (fs::metadata(src), fs::metadata(&other)) {
+                if src_meta.is_file()
+                    && other_meta.is_file()
+                    && same_content(src, &other, &src_meta, &other_meta)
+                {
+                    return Resolution::Dedupe(desired.to_path_buf());
+                }
+            }
+        }
+        _ => {}
+    }
+    Resolution::Move(reserve_unique_destination(src, desired, pattern, reserved))
+}
+
+/// Compare two files by size first, then by blake3 hash
+fn same_content(a: &Path, b: &Path, a_meta: &fs::Metadata, b_meta: &fs::Metadata) -> bool {
+    if a_meta.len() != b_meta.len() {
+        return false;
+    }
+    match (hash_file(a), hash_file(b)) {
+        (Ok(hash_a), Ok(hash_b)) => hash_a == hash_b,
+        _ => false,
+    }
+}
+
+/// Reserve a unique destination path.
+/// If `desired` already exists on disk or has been reserved in this run,
+/// generate a name from `pattern` (`{stem}`, `{n}` and `{ext}` placeholders)
+/// and return the first available path while recording it in `reserved` for `src`.
+pub fn reserve_unique_destination(
+    src: &Path,
+    desired: &Path,
+    pattern: &str,
+    reserved: &mut HashMap<PathBuf, PathBuf>,
+) -> PathBuf {
+    if !occupied(desired) && !reserved.contains_key(desired) {
+        reserved.insert(desired.to_path_buf(), src.to_path_buf());
+        return desired.to_path_buf();
+    }
+    let parent = desired.parent().unwrap_or_else(|| Path::new("."));
+    let stem = desired
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("file");
+    let ext = desired.extension().and_then(|s| s.to_str());
+    for i in 1.. {
+        let candidate = parent.join(apply_pattern(pattern, stem, ext, i));
+        if !occupied(&candidate) && !reserved.contains_key(&candidate) {
+            reserved.insert(candidate.clone(), src.to_path_buf());
+            return candidate;
+        }
+        if i == usize::MAX {
+            break;
+        }
+    }
+    // fallback to desired; record it
+    reserved.insert(desired.to_path_buf(), src.to_path_buf());
+    desired.to_path_buf()
+}
+
+/// Check whether something is at `path`. Links count even when they are dangling,
+/// `exists()` would follow them and let the move replace them.
+fn occupied(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+/// Build a file name from the conflict pattern.
+/// Names without an extension drop the `.{ext}` part of the pattern entirely.
+fn apply_pattern(pattern: &str, stem: &str, ext: Option<&str>, n: usize) -> String {
+    let pattern = match ext {
+        Some(e) if !e.is_empty() => pattern.replace("{ext}", e),
+        _ => pattern.replace(".{ext}", "").replace("{ext}", ""),
+    };
+    pattern
+        .replace("{stem}", stem)
+        .replace("{n}", &n.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_reserve_unique_destination_suffixes_collisions() {
+        let dir = tempdir().unwrap();
+        let desired = dir.path().join("notes.txt");
+        fs::write(&desired, "existing").unwrap();
+
+        let src = dir.path().join("inbox/notes.txt");
+        let mut reserved = HashMap::new();
+        let first =
+            reserve_unique_destination(&src, &desired, DEFAULT_CONFLICT_PATTERN, &mut reserved);
+        let second =
+            reserve_unique_destination(&src, &desired, DEFAULT_CONFLICT_PATTERN, &mut reserved);
+
+        assert_eq!(first, dir.path().join("notes-1.txt"));
+        assert_eq!(second, dir.path().join("notes-2.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_link_is_not_a_free_destination() {
+        let dir = tempdir().unwrap();
+        let desired = dir.path().join("notes.txt");
+        std::os::unix::fs::symlink(dir.path().join("missing.txt"), &desired).unwrap();
+
+        let mut reserved = HashMap::new();
+        assert_eq!(
+            reserve_unique_destination(
+                &dir.path().join("inbox/notes.txt"),
+                &desired,
+                DEFAULT_CONFLICT_PATTERN,
+                &mut reserved
+            ),
+            dir.path().join("notes-1.txt")
+        );
+    }
+
+    #[test]
+    fn test_apply_pattern() {
+        let pattern = "{stem} ({n}).{ext}";
+        assert_eq!(
+            apply_pattern(pattern, "notes", Some("txt"), 2),
+            "notes (2).txt"
+        );
+        assert_eq!(apply_pattern(pattern, "Makefile", None, 1), "Makefile (1)");
+        assert_eq!(
+            apply_pattern(DEFAULT_CONFLICT_PATTERN, "notes", None, 3),
+            "notes-3"
+        );
+    }
+
+    fn resolve(src: &Path, desired: &Path, strategy: ConflictStrategy) -> Resolution {
+        let mut reserved = HashMap::new();
+        resolve_destination(
+            src,
+            desired,
+            strategy,
+            DEFAULT_CONFLICT_PATTERN,
+            &mut reserved,
+        )
+    }
+
+    #[test]
+    fn test_no_conflict_moves_to_desired() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        fs::write(&src, "a").unwrap();
+        let desired = dir.path().join("docs/a.txt");
+
+        for strategy in [ConflictStrategy::Skip, ConflictStrategy::Overwrite] {
+            assert_eq!(
+                resolve(&src, &desired, strategy),
+                Resolution::Move(desired.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_strategies_on_existing_file() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        let desired = dir.path().join("existing.txt");
+        fs::write(&src, "larger content").unwrap();
+        fs::write(&desired, "small").unwrap();
+
+        assert_eq!(
+            resolve(&src, &desired, ConflictStrategy::Rename),
+            Resolution::Move(dir.path().join("existing-1.txt"))
+        );
+        assert!(matches!(
+            resolve(&src, &desired, ConflictStrategy::Skip),
+            Resolution::Skip(_)
+        ));
+        assert_eq!(
+            resolve(&src, &desired, ConflictStrategy::Overwrite),
+            Resolution::Overwrite(desired.clone())
+        );
+        assert_eq!(
+            resolve(&src, &desired, ConflictStrategy::KeepLarger),
+            Resolution::Overwrite(desired.clone())
+        );
+        assert!(matches!(
+            resolve(&desired, &src, ConflictStrategy::KeepLarger),
+            Resolution::Skip(_)
+        ));
+        // different content is never deduplicated
+        assert_eq!(
+            resolve(&src, &desired, ConflictStrategy::Dedupe),
+            Resolution::Move(dir.path().join("existing-1.txt"))
+        );
+    }
+
+    #[test]
+    fn test_dedupe_identical_content() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        let desired = dir.path().join("b.txt");
+        fs::write(&src, "same").unwrap();
+        fs::write(&desired, "same").unwrap();
+
+        assert_eq!(
+            resolve(&src, &desired, ConflictStrategy::Dedupe),
+            Resolution::Dedupe(desired)
+        );
+    }
+
+    #[test]
+    fn test_collision_within_run() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("a.txt");
+        let second = dir.path().join("other/a.txt");
+        fs::create_dir(dir.path().join("other")).unwrap();
+        fs::write(&first, "same").unwrap();
+        fs::write(&second, "same").unwrap();
+        let desired = dir.path().join("docs/a.txt");
+
+        let in_run = |strategy| {
+            let mut reserved = HashMap::new();
+            reserved.insert(desired.clone(), first.clone());
+            resolve_destination(
+                &second,
+                &desired,
+                strategy,
+                DEFAULT_CONFLICT_PATTERN,
+                &mut reserved,
+            )
+        };
+        // nothing is at the destination yet, so it cannot be overwritten
+        assert_eq!(
+            in_run(ConflictStrategy::Overwrite),
+            Resolution::Move(dir.path().join("docs/a-1.txt"))
+        );
+        assert!(matches!(
+            in_run(ConflictStrategy::Skip),
+            Resolution::Skip(_)
+        ));
+        // the content is compared against the file moving there
+        assert_eq!(
+            in_run(ConflictStrategy::Dedupe),
+            Resolution::Dedupe(desired.clone())
+        );
+        fs::write(&second, "different").unwrap();
+        assert_eq!(
+            in_run(ConflictStrategy::Dedupe),
+            Resolution::Move(dir.path().join("docs/a-1.txt"))
+        );
+    }
+}
@@ -0,0 +1,372 @@
+// This module detects the type of a file from its first bytes, for detect_content = true
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// How much of the file is read to detect its type. Office documents are zip
+/// archives, and the names of their first entries are needed to tell them apart.
+const HEADER_LEN: usize = 8 * 1024;
+
+/// Extensions that say nothing about the content, the detected type decides for them
+const GENERIC_EXTENSIONS: &[&str] = &["bin", "dat", "data", "tmp", "out", "unknown"];
+
+/// ISO media brands of mp4 video. Other formats use the same container (avif, cr3...).
+const MP4_BRANDS: &[&[u8; 4]] = &[
+    b"isom", b"iso2", b"iso4", b"iso5", b"iso6", b"mp41", b"mp42", b"avc1", b"M4V ", b"M4VH",
+    b"M4VP", b"3gp4", b"3gp5", b"3gp6", b"3g2a", b"dash", b"MSNV",
+];
+
+/// A type recognized from the content of a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detected {
+    pub mime: &'static str,
+    // extensions this type is usually saved with, the first one being the usual one
+    pub extensions: &'static [&'static str],
+}
+
+impl Detected {
+    /// Whether files of this type are saved with `extension`
+    pub fn fits(&self, extension: &str) -> bool {
+        self.extensions.contains(&extension)
+    }
+
+    /// The extension used to find presets: the file's own one if it fits the content,
+    /// otherwise the usual extension of the type
+    pub fn extension_for<'a>(&self, extension: Option<&'a str>) -> &'a str {
+        match extension {
+            Some(ext) if self.fits(ext) => ext,
+            _ => self.extensions[0],
+        }
+    }
+}
+
+struct Signature {
+    detected: Detected,
+    matches: fn(&[u8]) -> bool,
+}
+
+const fn signature(
+    mime: &'static str,
+    extensions: &'static [&'static str],
+    matches: fn(&[u8]) -> bool,
+) -> Signature {
+    Signature {
+        detected: Detected { mime, extensions },
+        matches,
+    }
+}
+
+/// Known signatures. More specific ones come first (e.g. office documents before zip).
+const SIGNATURES: &[Signature] = &[
+    // documents
+    signature("application/pdf", &["pdf"], |h| h.starts_with(b"%PDF-")),
+    signature("application/rtf", &["rtf"], |h| h.starts_with(b"{\\rtf")),
+    signature(
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        &["docx", "docm", "dotx"],
+        |h| is_zip(h) && contains(h, b"word/"),
+    ),
+    signature(
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        &["xlsx", "xlsm", "xltx"],
+        |h| is_zip(h) && contains(h, b"xl/"),
+    ),
+    signature(
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        &["pptx", "pptm", "potx"],
+        |h| is_zip(h) && contains(h, b"ppt/"),
+    ),
+    signature("application/vnd.oasis.opendocument.text", &["odt"], |h| {
+        zip_mimetype(h, b"application/vnd.oasis.opendocument.text")
+    }),
+    signature(
+        "application/vnd.oasis.opendocument.spreadsheet",
+        &["ods"],
+        |h| zip_mimetype(h, b"application/vnd.oasis.opendocument.spreadsheet"),
+    ),
+    signature(
+        "application/vnd.oasis.opendocument.presentation",
+        &["odp"],
+        |h| zip_mimetype(h, b"application/vnd.oasis.opendocument.presentation"),
+    ),
+    signature("application/epub+zip", &["epub"], |h| {
+        zip_mimetype(h, b"application/epub+zip")
+    }),
+    // legacy office documents share a single container format
+    signature(
+        "application/x-ole-storage",
+        &["doc", "xls", "ppt", "msg", "msi"],
+        |h| h.starts_with(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]),
+    ),
+    // images
+    signature("image/png", &["png"], |h| {
+        h.starts_with(b"\x89PNG\r\n\x1a\n")
+    }),
+    signature("image/jpeg", &["jpg", "jpeg", "jpe", "jfif"], |h| {
+        h.starts_with(&[0xFF, 0xD8, 0xFF])
+    }),
+    signature("image/gif", &["gif"], |h| {
+        h.starts_with(b"GIF87a") || h.starts_with(b"GIF89a")
+    }),
+    signature("image/webp", &["webp"], |h| riff(h, b"WEBP")),
+    signature("image/tiff", &["tif", "tiff"], |h| {
+        h.starts_with(b"II*\0") || h.starts_with(b"MM\0*")
+    }),
+    signature("image/bmp", &["bmp"], |h| {
+        // "BM" alone is too common, the reserved header fields must be zero
+        h.starts_with(b"BM") && h.get(6..10) == Some(&[0, 0, 0, 0])
+    }),
+    signature("image/vnd.microsoft.icon", &["ico"], is_ico),
+    signature("image/vnd.adobe.photoshop", &["psd"], |h| {
+        h.starts_with(b"8BPS")
+    }),
+    signature("image/heic", &["heic", "heif"], |h| {
+        ftyp(h, &[b"heic", b"heix", b"mif1", b"msf1"])
+    }),
+    // audio and video
+    signature("video/quicktime", &["mov", "qt"], |h| ftyp(h, &[b"qt  "])),
+    signature("audio/mp4", &["m4a", "m4b"], |h| {
+        ftyp(h, &[b"M4A ", b"M4B "])
+    }),
+    signature("video/mp4", &["mp4", "m4v", "3gp"], |h| ftyp(h, MP4_BRANDS)),
+    signature("video/x-matroska", &["mkv", "webm", "mka"], |h| {
+        h.starts_with(&[0x1A, 0x45, 0xDF, 0xA3])
+    }),
+    signature("video/x-msvideo", &["avi"], |h| riff(h, b"AVI ")),
+    signature("audio/wav", &["wav"], |h| riff(h, b"WAVE")),
+    signature("audio/flac", &["flac"], |h| h.starts_with(b"fLaC")),
+    signature("audio/ogg", &["ogg", "oga", "opus"], |h| {
+        h.starts_with(b"OggS")
+    }),
+    signature("audio/mpeg", &["mp3"], |h| {
+        h.starts_with(b"ID3") || h.starts_with(&[0xFF, 0xFB]) || h.starts_with(&[0xFF, 0xF3])
+    }),
+    // archives
+    signature(
+        "application/zip",
+        &["zip", "jar", "apk", "aab", "ipa", "whl", "xpi", "nupkg"],
+        is_zip,
+    ),
+    signature("application/x-7z-compressed", &["7z"], |h| {
+        h.starts_with(&[b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C])
+    }),
+    signature("application/vnd.rar", &["rar"], |h| {
+        h.starts_with(b"Rar!\x1a\x07")
+    }),
+    signature("application/gzip", &["gz", "tgz"], |h| {
+        h.starts_with(&[0x1F, 0x8B])
+    }),
+    signature("application/x-bzip2", &["bz2", "tbz2"], |h| {
+        h.starts_with(b"BZh")
+    }),
+    signature("application/x-xz", &["xz", "txz"], |h| {
+        h.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0])
+    }),
+    signature("application/zstd", &["zst"], |h| {
+        h.starts_with(&[0x28, 0xB5, 0x2F, 0xFD])
+    }),
+    signature("application/x-tar", &["tar"], |h| {
+        h.get(257..262) == Some(b"ustar")
+    }),
+    // executables and data
+    signature("application/x-executable", &["elf", "so", "o"], |h| {
+        h.starts_with(b"\x7fELF")
+    }),
+    signature(
+        "application/vnd.microsoft.portable-executable",
+        &["exe", "dll", "sys"],
+        is_pe,
+    ),
+    signature("application/wasm", &["wasm"], |h| h.starts_with(b"\0asm")),
+    signature(
+        "application/vnd.sqlite3",
+        &["sqlite", "db", "sqlite3"],
+        |h| h.starts_with(b"SQLite format 3\0"),
+    ),
+];
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+fn is_zip(h: &[u8]) -> bool {
+    h.starts_with(b"PK\x03\x04")
+}
+
+fn u16_at(h: &[u8], offset: usize) -> Option<u16> {
+    h.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(h: &[u8], offset: usize) -> Option<u32> {
+    h.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// "\0\0\1\0" alone is too common: the icon count and the first directory entry
+/// (reserved byte, color planes, image size and offset) must make sense too
+fn is_ico(h: &[u8]) -> bool {
+    if !h.starts_with(&[0, 0, 1, 0]) {
+        return false;
+    }
+    let (Some(count), Some(planes), Some(size), Some(offset)) =
+        (u16_at(h, 4), u16_at(h, 10), u32_at(h, 14), u32_at(h, 18))
+    else {
+        return false;
+    };
+    // the images follow the 6 byte header and the 16 byte entry of each of them
+    count > 0 && h[9] == 0 && planes <= 1 && size > 0 && offset >= 6 + 16 * u32::from(count)
+}
+
+/// DOS executables start with "MZ". Windows ones point at 0x3C to a "PE\0\0" header.
+fn is_pe(h: &[u8]) -> bool {
+    h.starts_with(b"MZ")
+        && u32_at(h, 0x3C)
+            .and_then(|offset| h.get(offset as usize..))
+            .is_some_and(|pe| pe.starts_with(b"PE\0\0"))
+}
+
+/// OpenDocument and EPUB files start with an uncompressed "mimetype" entry
+fn zip_mimetype(h: &[u8], mime: &[u8]) -> bool {
+    is_zip(h) && h.get(30..38) == Some(b"mimetype") && h[38..].starts_with(mime)
+}
+
+/// RIFF containers carry their format at offset 8
+fn riff(h: &[u8], format: &[u8; 4]) -> bool {
+    h.starts_with(b"RIFF") && h.get(8..12) == Some(format)
+}
+
+/// ISO media files (mp4, mov, heic...) have an "ftyp" box with a brand at offset 8
+fn ftyp(h: &[u8], brands: &[&[u8; 4]]) -> bool {
+    h.get(4..8) == Some(b"ftyp")
+        && h.get(8..12)
+            .is_some_and(|brand| brands.iter().any(|b| brand == *b))
+}
+
+/// Whether an extension is too generic to tell anything about the file
+pub fn is_generic(extension: &str) -> bool {
+    GENERIC_EXTENSIONS.contains(&extension)
+}
+
+/// Detect the type of the file at `path` from its first bytes
+pub fn detect(path: &Path) -> Option<Detected> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    File::open(path)
+        .ok()?
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+    detect_bytes(&header)
+}
+
+fn detect_bytes(header: &[u8]) -> Option<Detected> {
+    SIGNATURES
+        .iter()
+        .find(|s| (s.matches)(header))
+        .map(|s| s.detected)
+}
+
+/// Check a MIME type against a preset pattern like "image/png" or "image/*"
+pub fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(kind) => mime
+            .split_once('/')
+            .is_some_and(|(k, _)| k.eq_ignore_ascii_case(kind)),
+        None => pattern.eq_ignore_ascii_case(mime),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mime(header: &[u8]) -> Option<&'static str> {
+        detect_bytes(header).map(|d| d.mime)
+    }
+
+    #[test]
+    fn test_detect_signatures() {
+        assert_eq!(mime(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(mime(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(mime(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("image/jpeg"));
+        assert_eq!(mime(b"\0\0\0\x18ftypmp42\0\0\0\0"), Some("video/mp4"));
+        assert_eq!(mime(b"\0\0\0\x14ftypqt  \0\0\0\0"), Some("video/quicktime"));
+        assert_eq!(
+            mime(b"\x7fELF\x02\x01\x01"),
+            Some("application/x-executable")
+        );
+        // other formats in the same container are not video
+        assert_eq!(mime(b"\0\0\0\x1cftypavif\0\0\0\0"), None);
+        assert_eq!(mime(b"\0\0\0\x18ftypcrx \0\0\0\x01"), None);
+        assert_eq!(mime(b"hello world"), None);
+        assert_eq!(mime(b""), None);
+    }
+
+    #[test]
+    fn test_detect_zip_based_formats() {
+        let mut docx = b"PK\x03\x04".to_vec();
+        docx.extend_from_slice(&[0; 26]);
+        docx.extend_from_slice(b"[Content_Types].xml ... word/document.xml");
+        assert_eq!(
+            mime(&docx),
+            Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+        );
+
+        let mut odt = b"PK\x03\x04".to_vec();
+        odt.extend_from_slice(&[0; 26]);
+        odt.extend_from_slice(b"mimetypeapplication/vnd.oasis.opendocument.text");
+        assert_eq!(mime(&odt), Some("application/vnd.oasis.opendocument.text"));
+
+        let mut zip = b"PK\x03\x04".to_vec();
+        zip.extend_from_slice(&[0; 26]);
+        zip.extend_from_slice(b"notes.txt");
+        assert_eq!(mime(&zip), Some("application/zip"));
+    }
+
+    #[test]
+    fn test_detect_checks_ico_and_pe_headers() {
+        // one 16x16 icon, its image right after the directory
+        let mut ico = vec![0, 0, 1, 0, 1, 0, 16, 16, 0, 0, 1, 0, 32, 0];
+        ico.extend_from_slice(&1128u32.to_le_bytes());
+        ico.extend_from_slice(&22u32.to_le_bytes());
+        assert_eq!(mime(&ico), Some("image/vnd.microsoft.icon"));
+        // no icon, or an image overlapping the directory
+        assert_eq!(mime(&[0, 0, 1, 0, 0, 0, 16, 16, 0, 0, 1, 0, 32, 0]), None);
+        ico[18] = 6;
+        assert_eq!(mime(&ico), None);
+
+        let mut exe = vec![0; 0x80];
+        exe[..2].copy_from_slice(b"MZ");
+        exe[0x3C] = 0x40;
+        exe[0x40..0x44].copy_from_slice(b"PE\0\0");
+        assert_eq!(
+            mime(&exe),
+            Some("application/vnd.microsoft.portable-executable")
+        );
+        // "MZ" followed by text is not an executable
+        assert_eq!(mime(b"MZ is a postal code prefix"), None);
+        exe[0x3C] = 0x70;
+        assert_eq!(mime(&exe), None);
+    }
+
+    #[test]
+    fn test_extension_for() {
+        let png = detect_bytes(b"\x89PNG\r\n\x1a\n").unwrap();
+        assert_eq!(png.extension_for(Some("bin")), "png");
+        assert_eq!(png.extension_for(None), "png");
+        let jpeg = detect_bytes(&[0xFF, 0xD8, 0xFF]).unwrap();
+        assert_eq!(jpeg.extension_for(Some("jpeg")), "jpeg");
+        let ole = detect_bytes(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]).unwrap();
+        assert!(ole.fits("msi"));
+        assert!(is_generic("bin"));
+        assert!(!is_generic("ai"));
+    }
+
+    #[test]
+    fn test_mime_matches() {
+        assert!(mime_matches("image/*", "image/png"));
+        assert!(mime_matches("application/PDF", "application/pdf"));
+        assert!(!mime_matches("image/*", "video/mp4"));
+        assert!(!mime_matches("image/png", "image/jpeg"));
+    }
+}
@@ -0,0 +1,418 @@
+// This module copies files for moves that cross filesystems, and checks each copy
+// according to the `verify` setting before its source may be deleted
+use std::collections::hash_map::RandomState;
+use std::fs::{self, File};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::config::config_processor::{SymlinkPolicy, VerifyMode};
+use crate::core::sort::preserve::preserve_metadata;
+use crate::core::sort::sort::hash_file;
+use crate::core::sort::symlinks::MovedPaths;
+use crate::core::sort::throttle::Throttle;
+
+/// Size of the chunks copied between two throttle checks
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Size of each chunk hashed by `sampled`
+const SAMPLE_SIZE: u64 = 256 * 1024;
+
+/// Chunks hashed at random offsets by `sampled`, on top of the head and the tail
+const RANDOM_SAMPLES: usize = 4;
+
+/// Files up to this size are checked in full by `sampled`, sampling would read most of them
+const SAMPLED_MIN_SIZE: u64 = 8 * 1024 * 1024;
+
+/// How the copies of a run are made, when a move crosses filesystems
+#[derive(Debug)]
+pub struct CopyOptions {
+    pub verify: VerifyMode,
+    // bandwidth limit shared by every copy of the run
+    pub throttle: Option<Throttle>,
+    // give copies the times, owner, xattrs and permissions of their source
+    pub preserve: bool,
+    // links are recreated as links, or copied as what they point to with `follow`
+    pub symlinks: SymlinkPolicy,
+    // where the entries of the run go, recreated links point to where their target moves
+    pub moved: MovedPaths,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        CopyOptions {
+            verify: VerifyMode::default(),
+            throttle: None,
+            preserve: true,
+            symlinks: SymlinkPolicy::default(),
+            moved: MovedPaths::default(),
+        }
+    }
+}
+
+/// Copy a file and check the copy according to `verify`. A copy failing the check is
+/// removed, the source is never touched.
+pub fn copy_verified(src: &Path, dst: &Path, options: &CopyOptions) -> Result<(), String> {
+    // read before copying, the copy and its check update the access time
+    let src_meta = fs::metadata(src).map_err(|e| format!("stat src failed: {}", e))?;
+    // the full check hashes the source while it is copied, so it is only read once
+    let full = options.verify == VerifyMode::Full;
+    let (copied, src_hash) = copy_file(src, dst, options.throttle.as_ref(), full)
+        .map_err(|e| format!("copy failed: {}", e))?;
+
+    let throttle = options.throttle.as_ref();
+    let checked = check_copy(src, dst, options.verify, copied, src_hash, throttle);
+    if checked.is_err() {
+        let _ = fs::remove_file(dst);
+        return checked;
+    }
+    // the copy is writable until now, so the metadata can still be set on it.
+    // the permissions of the source come last, like fs::copy keeps them
+    if options.preserve {
+        warn_not_preserved(src, &preserve_metadata(src, dst, &src_meta));
+    } else if let Err(e) = fs::set_permissions(dst, src_meta.permissions()) {
+        warn_not_preserved(src, &[format!("permissions ({})", e)]);
+    }
+    Ok(())
+}
+
+/// Warn about the metadata `preserve_metadata` could not carry over to the copy of `src`
+pub fn warn_not_preserved(src: &Path, lost: &[String]) {
+    for what in lost {
+        log::warn!("{}: could not preserve {}", src.display(), what);
+    }
+}
+
+/// Copy a file like `fs::copy`, held under the limit when there is one, and hashing
+/// what is read when `hash` is set. The copy is left writable by its owner.
+fn copy_file(
+    src: &Path,
+    dst: &Path,
+    throttle: Option<&Throttle>,
+    hash: bool,
+) -> io::Result<(u64, Option<blake3::Hash>)> {
+    if throttle.is_none() && !hash {
+        let copied = fs::copy(src, dst)?;
+        make_writable(dst)?;
+        return Ok((copied, None));
+    }
+
+    let mut reader = File::open(src)?;
+    let mut writer = File::create(dst)?;
+    let mut hasher = hash.then(blake3::Hasher::new);
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut copied = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        if let Some(hasher) = &mut hasher {
+            hasher.update(&buffer[..read]);
+        }
+        copied += read as u64;
+        if let Some(throttle) = throttle {
+            throttle.consume(read as u64);
+        }
+    }
+    writer.flush()?;
+    Ok((copied, hasher.map(|h| h.finalize())))
+}
+
+/// Let the owner write to a copy `fs::copy` made read-only like its source, otherwise
+/// its times and extended attributes could not be set
+fn make_writable(path: &Path) -> io::Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    if !permissions.readonly() {
+        return Ok(());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    // Windows only has the read-only attribute
+    #[cfg(not(unix))]
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(path, permissions)
+}
+
+/// Check a copy according to `verify`. Its reads count against the bandwidth limit too.
+fn check_copy(
+    src: &Path,
+    dst: &Path,
+    verify: VerifyMode,
+    copied: u64,
+    src_hash: Option<blake3::Hash>,
+    throttle: Option<&Throttle>,
+) -> Result<(), String> {
+    if verify == VerifyMode::None {
+        return Ok(());
+    }
+
+    // verify size first (quick check)
+    let src_len = fs::metadata(src)
+        .map_err(|e| format!("stat src failed: {}", e))?
+        .len();
+    let dst_len = fs::metadata(dst)
+        .map_err(|e| format!("stat dst failed: {}", e))?
+        .len();
+    if src_len != dst_len || copied != src_len {
+        return Err("size mismatch after copy".into());
+    }
+
+    match verify {
+        VerifyMode::None | VerifyMode::Size => Ok(()),
+        VerifyMode::Sampled if src_len > SAMPLED_MIN_SIZE => {
+            let offsets = sample_offsets(src_len);
+            let src_sample = sampled_hash(src, &offsets, throttle)
+                .map_err(|e| format!("hash src failed: {}", e))?;
+            let dst_sample = sampled_hash(dst, &offsets, throttle)
+                .map_err(|e| format!("hash dst failed: {}", e))?;
+            if src_sample == dst_sample {
+                return Ok(());
+            }
+            log::debug!(
+                "{}: sampled check failed, checking the whole copy",
+                src.display()
+            );
+            check_full(src, dst, None, throttle)
+        }
+        VerifyMode::Sampled => check_full(src, dst, None, throttle),
+        VerifyMode::Full => check_full(src, dst, src_hash, throttle),
+    }
+}
+
+/// Compare the blake3 hashes of both files, reusing the one computed during the copy
+fn check_full(
+    src: &Path,
+    dst: &Path,
+    src_hash: Option<blake3::Hash>,
+    throttle: Option<&Throttle>,
+) -> Result<(), String> {
+    let src_hash = match src_hash {
+        Some(hash) => hash,
+        None => hash_throttled(src, throttle).map_err(|e| format!("hash src failed: {}", e))?,
+    };
+    let dst_hash = hash_throttled(dst, throttle).map_err(|e| format!("hash dst failed: {}", e))?;
+    if src_hash != dst_hash {
+        return Err("hash mismatch after copy".into());
+    }
+    Ok(())
+}
+
+/// Hash a whole file, held under the limit when there is one
+fn hash_throttled(path: &Path, throttle: Option<&Throttle>) -> io::Result<blake3::Hash> {
+    let Some(throttle) = throttle else {
+        return hash_file(path);
+    };
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        throttle.consume(read as u64);
+    }
+    Ok(hasher.finalize())
+}
+
+/// Offsets of the chunks hashed by `sampled`: the head, the tail and a few random ones.
+/// Random offsets differ between runs so a corruption is never always missed.
+fn sample_offsets(len: u64) -> Vec<u64> {
+    let last = len.saturating_sub(SAMPLE_SIZE);
+    let mut offsets = vec![0, last];
+    let mut random = RandomState::new().build_hasher();
+    for n in 0..RANDOM_SAMPLES {
+        random.write_usize(n);
+        offsets.push(random.finish() % (last + 1));
+    }
+    offsets
+}
+
+/// Hash the chunks of the file starting at `offsets`
+fn sampled_hash(
+    path: &Path,
+    offsets: &[u64],
+    throttle: Option<&Throttle>,
+) -> io::Result<blake3::Hash> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = Vec::with_capacity(SAMPLE_SIZE as usize);
+    for offset in offsets {
+        file.seek(SeekFrom::Start(*offset))?;
+        buffer.clear();
+        (&mut file).take(SAMPLE_SIZE).read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+        if let Some(throttle) = throttle {
+            throttle.consume(buffer.len() as u64);
+        }
+    }
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_every_mode_accepts_a_good_copy() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("video.mkv");
+        // large enough to be sampled
+        let content: Vec<u8> = (0..SAMPLED_MIN_SIZE + 4096).map(|n| n as u8).collect();
+        fs::write(&src, &content).unwrap();
+
+        for verify in [
+            VerifyMode::None,
+            VerifyMode::Size,
+            VerifyMode::Sampled,
+            VerifyMode::Full,
+        ] {
+            let dst = dir.path().join("copy.mkv");
+            let options = CopyOptions {
+                verify,
+                ..Default::default()
+            };
+            copy_verified(&src, &dst, &options).unwrap();
+            assert_eq!(fs::read(&dst).unwrap(), content);
+            fs::remove_file(&dst).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_only_source_keeps_its_metadata() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("contract.pdf");
+        fs::write(&src, "pdf").unwrap();
+        #[cfg(target_os = "linux")]
+        let xattrs = xattr::set(&src, "user.origin", b"mail").is_ok();
+        let mut permissions = fs::metadata(&src).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&src, permissions).unwrap();
+
+        // through fs::copy, and through the chunked copy
+        for throttle in [None, Some(Throttle::new(u64::MAX))] {
+            let dst = dir.path().join("copy.pdf");
+            let src_meta = fs::metadata(&src).unwrap();
+            copy_file(&src, &dst, throttle.as_ref(), false).unwrap();
+            assert!(!fs::metadata(&dst).unwrap().permissions().readonly());
+            assert!(preserve_metadata(&src, &dst, &src_meta).is_empty());
+            assert!(fs::metadata(&dst).unwrap().permissions().readonly());
+            #[cfg(target_os = "linux")]
+            if xattrs {
+                assert_eq!(
+                    xattr::get(&dst, "user.origin").unwrap(),
+                    Some(b"mail".to_vec())
+                );
+            }
+            fs::remove_file(&dst).unwrap();
+        }
+
+        // without preserve_metadata the copy still gets the permissions of its source
+        let dst = dir.path().join("plain.pdf");
+        let options = CopyOptions {
+            preserve: false,
+            ..Default::default()
+        };
+        copy_verified(&src, &dst, &options).unwrap();
+        assert!(fs::metadata(&dst).unwrap().permissions().readonly());
+    }
+
+    #[test]
+    fn test_corrupted_copy_is_rejected() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("notes.txt");
+        let dst = dir.path().join("copy.txt");
+        fs::write(&src, "original").unwrap();
+        fs::write(&dst, "corruptd").unwrap();
+
+        // same size, different content
+        assert!(check_copy(&src, &dst, VerifyMode::Size, 8, None, None).is_ok());
+        assert!(check_copy(&src, &dst, VerifyMode::Sampled, 8, None, None).is_err());
+        assert!(check_copy(&src, &dst, VerifyMode::Full, 8, None, None).is_err());
+        // the hash computed during the copy is trusted for the source
+        let hash = blake3::hash(b"corruptd");
+        assert!(check_copy(&src, &dst, VerifyMode::Full, 8, Some(hash), None).is_ok());
+        assert!(check_copy(&src, &dst, VerifyMode::Size, 7, None, None).is_err());
+    }
+
+    #[test]
+    fn test_sampled_check_escalates_to_full() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("video.mkv");
+        let dst = dir.path().join("copy.mkv");
+        let len = SAMPLED_MIN_SIZE * 2;
+        let content: Vec<u8> = (0..len).map(|n| (n % 251) as u8).collect();
+        fs::write(&src, &content).unwrap();
+
+        // a corrupted head is always sampled, the full check then rejects the copy
+        let mut corrupted = content.clone();
+        corrupted[10] ^= 0xFF;
+        fs::write(&dst, &corrupted).unwrap();
+        assert!(check_copy(&src, &dst, VerifyMode::Sampled, len, None, None).is_err());
+
+        fs::write(&dst, &content).unwrap();
+        assert!(check_copy(&src, &dst, VerifyMode::Sampled, len, None, None).is_ok());
+    }
+
+    #[test]
+    fn test_sample_offsets_stay_in_the_file() {
+        let len = SAMPLED_MIN_SIZE * 3;
+        let offsets = sample_offsets(len);
+        assert_eq!(offsets.len(), 2 + RANDOM_SAMPLES);
+        assert_eq!(offsets[0], 0);
+        assert_eq!(offsets[1], len - SAMPLE_SIZE);
+        assert!(offsets.iter().all(|o| o + SAMPLE_SIZE <= len));
+    }
+
+    #[test]
+    fn test_throttled_copy_respects_the_limit() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("big.bin");
+        let dst = dir.path().join("copy.bin");
+        fs::write(&src, vec![7u8; 200 * 1024]).unwrap();
+
+        // 200 KiB at 1 MiB/s takes about 200ms, minus the 256 KiB burst
+        let options = CopyOptions {
+            verify: VerifyMode::Full,
+            throttle: Some(Throttle::new(1024 * 1024)),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        copy_verified(&src, &dst, &options).unwrap();
+        // the copy and the hash of the copy: 400 KiB, 144 KiB over the burst
+        assert!(start.elapsed() >= Duration::from_millis(120));
+        assert_eq!(fs::read(&src).unwrap(), fs::read(&dst).unwrap());
+    }
+
+    #[test]
+    fn test_verify_reads_are_throttled() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("big.bin");
+        let dst = dir.path().join("copy.bin");
+        fs::write(&src, vec![7u8; 200 * 1024]).unwrap();
+        fs::write(&dst, vec![7u8; 200 * 1024]).unwrap();
+
+        // both files are read, 400 KiB at 1 MiB/s once the 256 KiB burst is spent
+        let throttle = Throttle::new(1024 * 1024);
+        let start = Instant::now();
+        check_copy(
+            &src,
+            &dst,
+            VerifyMode::Sampled,
+            200 * 1024,
+            None,
+            Some(&throttle),
+        )
+        .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(120));
+    }
+}
@@ -0,0 +1,118 @@
+// This module matches entries against the [file_exclusions] section and per-preset exclude lists
+use std::path::Path;
+
+use crate::config::config_pattern::Pattern;
+use crate::config::config_processor::ExclusionConfig;
+
+/// A list of compiled patterns, either the global one or a preset's `exclude`
+#[derive(Debug, Clone, Default)]
+pub struct ExclusionSet {
+    patterns: Vec<Pattern>,
+}
+
+impl ExclusionSet {
+    pub fn new(patterns: &[String], case_sensitive: bool) -> Result<Self, String> {
+        let patterns = patterns
+            .iter()
+            .map(|p| Pattern::new(p, case_sensitive))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ExclusionSet { patterns })
+    }
+
+    /// Return the first pattern excluding `path`, if any
+    pub fn matching(&self, target: &Path, path: &Path) -> Option<&str> {
+        if self.patterns.is_empty() {
+            return None;
+        }
+        let name = path.file_name()?.to_string_lossy();
+        // always match relative paths with '/' so patterns are portable
+        let relative = path
+            .strip_prefix(target)
+            .unwrap_or(path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.patterns
+            .iter()
+            .find(|p| p.is_match(&name, &relative))
+            .map(Pattern::source)
+    }
+}
+
+/// Check a directory name against the `directories` ignore list
+pub fn is_excluded_directory(exclusions: &ExclusionConfig, name: &str) -> bool {
+    exclusions.directories.iter().any(|d| {
+        if exclusions.case_sensitive {
+            d == name
+        } else {
+            d.eq_ignore_ascii_case(name)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(patterns: &[&str], case_sensitive: bool) -> ExclusionSet {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        ExclusionSet::new(&patterns, case_sensitive).unwrap()
+    }
+
+    #[test]
+    fn test_glob_matches_name() {
+        let target = Path::new("/t");
+        let exclusions = set(&["*.tmp", "Thumbs.db"], false);
+        assert_eq!(
+            exclusions.matching(target, Path::new("/t/a/b.TMP")),
+            Some("*.tmp")
+        );
+        assert_eq!(
+            exclusions.matching(target, Path::new("/t/thumbs.db")),
+            Some("Thumbs.db")
+        );
+        assert_eq!(exclusions.matching(target, Path::new("/t/b.txt")), None);
+
+        let case_sensitive = set(&["*.tmp"], true);
+        assert_eq!(case_sensitive.matching(target, Path::new("/t/b.TMP")), None);
+    }
+
+    #[test]
+    fn test_glob_with_separator_matches_relative_path() {
+        let target = Path::new("/t");
+        let exclusions = set(&["build/*"], false);
+        assert!(exclusions
+            .matching(target, Path::new("/t/build/a.o"))
+            .is_some());
+        assert!(exclusions
+            .matching(target, Path::new("/t/src/build/a.o"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_regex_patterns() {
+        let target = Path::new("/t");
+        let exclusions = set(&[r"regex:^~\$.*\.docx$", "regex:^cache/"], false);
+        assert!(exclusions
+            .matching(target, Path::new("/t/~$report.docx"))
+            .is_some());
+        assert!(exclusions
+            .matching(target, Path::new("/t/cache/x.bin"))
+            .is_some());
+        assert!(exclusions
+            .matching(target, Path::new("/t/report.docx"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_excluded_directory_case() {
+        let mut exclusions = ExclusionConfig {
+            directories: vec!["node_modules".to_string()],
+            ..Default::default()
+        };
+        assert!(is_excluded_directory(&exclusions, "Node_Modules"));
+        exclusions.case_sensitive = true;
+        assert!(!is_excluded_directory(&exclusions, "Node_Modules"));
+    }
+}
@@ -0,0 +1,54 @@
+// This module tells hidden entries apart: dot-prefixed names, and the hidden attribute
+// of the platforms having one (Windows, macOS)
+use std::fs::Metadata;
+use std::path::Path;
+
+/// Whether the entry is hidden, by its name or its attributes when `metadata` is given
+pub fn is_hidden(path: &Path, metadata: Option<&Metadata>) -> bool {
+    let dot_name = path
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+    dot_name || metadata.is_some_and(has_hidden_attribute)
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(target_os = "macos")]
+fn has_hidden_attribute(metadata: &Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    // set by `chflags hidden`, Finder hides these
+    const UF_HIDDEN: u32 = 0x8000;
+    metadata.st_flags() & UF_HIDDEN != 0
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn has_hidden_attribute(_metadata: &Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_is_hidden() {
+        assert!(is_hidden(Path::new("/home/user/.bashrc"), None));
+        assert!(is_hidden(Path::new("downloads/.config"), None));
+        assert!(is_hidden(Path::new(".invite.ics"), None));
+        assert!(!is_hidden(Path::new("/home/.user/invite.ics"), None));
+        assert!(!is_hidden(Path::new("movie.torrent"), None));
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "notes").unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        assert!(!is_hidden(&path, Some(&metadata)));
+    }
+}
@@ -0,0 +1,195 @@
+// This module recognizes entries that are still being written: unfinished downloads,
+// recently modified files and files another process has open, alone or inside a folder
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+use crate::config::config_processor::ExclusionConfig;
+
+/// Extensions browsers, download managers and torrent clients give unfinished files.
+/// These entries are always skipped.
+pub const IN_PROGRESS_EXTENSIONS: &[&str] = &[
+    "part",
+    "partial",
+    "crdownload",
+    "download",
+    "opdownload",
+    "!qb",
+    "!ut",
+    "aria2",
+];
+
+/// Check whether the name marks an unfinished download, e.g. "movie.mkv.part"
+pub fn is_in_progress_name(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IN_PROGRESS_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Why a file must be left alone for now, if it is still being written
+pub fn busy_reason(
+    path: &Path,
+    metadata: Option<&fs::Metadata>,
+    exclusions: &ExclusionConfig,
+    open_files: &HashSet<PathBuf>,
+) -> Option<String> {
+    if !exclusions.min_age.is_zero() {
+        let age = metadata
+            .and_then(|m| m.modified().ok())
+            // a modification time in the future counts as just modified
+            .map(|t| SystemTime::now().duration_since(t).unwrap_or_default());
+        if age.is_some_and(|age| age < exclusions.min_age) {
+            return Some(format!(
+                "modified less than {} seconds ago",
+                exclusions.min_age.as_secs()
+            ));
+        }
+    }
+
+    if exclusions.skip_open_files && is_open(path, open_files) {
+        return Some("open in another process".to_string());
+    }
+
+    None
+}
+
+/// Why a directory moved as a whole must be left alone for now: it, or the newest entry
+/// inside it, is still being written
+pub fn busy_dir_reason(
+    dir: &Path,
+    exclusions: &ExclusionConfig,
+    open_files: &HashSet<PathBuf>,
+) -> Option<String> {
+    if exclusions.min_age.is_zero() && !exclusions.skip_open_files {
+        return None;
+    }
+    let metadata = fs::metadata(dir).ok();
+    if let Some(reason) = busy_reason(dir, metadata.as_ref(), exclusions, open_files) {
+        return Some(reason);
+    }
+
+    // a download or a copy into the folder writes to its newest entry
+    let (metadata, newest) = WalkDir::new(dir)
+        .min_depth(1)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| Some((e.metadata().ok()?, e.into_path())))
+        .max_by_key(|(m, _)| m.modified().ok())?;
+    let reason = busy_reason(&newest, Some(&metadata), exclusions, open_files)?;
+    let name = newest.strip_prefix(dir).unwrap_or(&newest);
+    Some(format!("{} inside is {}", name.display(), reason))
+}
+
+fn is_open(path: &Path, open_files: &HashSet<PathBuf>) -> bool {
+    if open_files.is_empty() {
+        return false;
+    }
+    // descriptors point at the resolved path
+    open_files.contains(path) || fs::canonicalize(path).is_ok_and(|p| open_files.contains(&p))
+}
+
+/// Every file some process has open, read from /proc/<pid>/fd once per run.
+/// Only the processes of the current user are visible, which covers browsers and
+/// download managers.
+#[cfg(target_os = "linux")]
+pub fn open_files() -> HashSet<PathBuf> {
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return HashSet::new();
+    };
+    processes
+        .flatten()
+        .filter(|p| {
+            p.file_name()
+                .to_str()
+                .is_some_and(|n| n.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .filter_map(|p| fs::read_dir(p.path().join("fd")).ok())
+        .flat_map(|fds| fds.flatten())
+        .filter_map(|fd| fs::read_link(fd.path()).ok())
+        .filter(|target| target.is_absolute())
+        .collect()
+}
+
+/// Open files cannot be listed on this platform, so none are skipped
+#[cfg(not(target_os = "linux"))]
+pub fn open_files() -> HashSet<PathBuf> {
+    HashSet::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_in_progress_names() {
+        for name in [
+            "movie.mkv.part",
+            "setup.exe.crdownload",
+            "photo.jpg.download",
+            "album.zip.!qB",
+            "iso.aria2",
+        ] {
+            assert!(is_in_progress_name(Path::new(name)), "{}", name);
+        }
+        assert!(!is_in_progress_name(Path::new("movie.mkv")));
+        assert!(!is_in_progress_name(Path::new("part")));
+    }
+
+    #[test]
+    fn test_min_age() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "notes").unwrap();
+        let metadata = fs::metadata(&file).unwrap();
+
+        let mut exclusions = ExclusionConfig::default();
+        let open = HashSet::new();
+        assert_eq!(
+            busy_reason(&file, Some(&metadata), &exclusions, &open),
+            None
+        );
+
+        exclusions.min_age = Duration::from_secs(60);
+        assert!(busy_reason(&file, Some(&metadata), &exclusions, &open).is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_open_files() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("download.iso");
+        let handle = fs::File::create(&file).unwrap();
+
+        let exclusions = ExclusionConfig {
+            skip_open_files: true,
+            ..Default::default()
+        };
+        let open = open_files();
+        assert!(busy_reason(&file, None, &exclusions, &open).is_some());
+        drop(handle);
+        assert_eq!(busy_reason(&file, None, &exclusions, &open_files()), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_open_file_inside_directory() {
+        let dir = tempdir().unwrap();
+        let album = dir.path().join("album");
+        fs::create_dir_all(album.join("disc 1")).unwrap();
+        fs::write(album.join("cover.jpg"), "cover").unwrap();
+        let handle = fs::File::create(album.join("disc 1/track.flac")).unwrap();
+
+        let exclusions = ExclusionConfig {
+            skip_open_files: true,
+            ..Default::default()
+        };
+        let reason = busy_dir_reason(&album, &exclusions, &open_files()).unwrap();
+        assert!(reason.contains("track.flac"), "{}", reason);
+        drop(handle);
+        assert_eq!(busy_dir_reason(&album, &exclusions, &open_files()), None);
+    }
+}
@@ -1 +1,17 @@
+pub mod conflict;
+pub mod content;
+pub mod copy;
+pub mod exclusions;
+pub mod hidden;
+pub mod in_progress;
+pub mod naming;
+pub mod preserve;
+pub mod project;
+pub mod protected;
+pub mod report;
+pub mod rules;
+#[allow(clippy::module_inception)]
 pub mod sort;
+pub mod symlinks;
+pub mod throttle;
+pub mod walk;
@@ -0,0 +1,237 @@
+// This module renames destination entries according to the [file_naming] section
+use crate::config::config_processor::{NamingConfig, RenameMethod, INVALID_CHARS};
+
+/// Device names Windows reserves, whatever the extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Extensions made of two parts, kept together so "archive.tar.gz" keeps its ".tar.gz"
+const MULTI_PART_EXTENSIONS: &[&str] = &["tar.gz", "tar.bz2", "tar.xz", "tar.zst"];
+
+/// Used when nothing is left of a name after sanitizing
+const EMPTY_NAME: &str = "_";
+
+/// Apply the naming settings to the name of an entry. The style applies to the stem,
+/// the extension is kept as is (directories have no extension).
+pub fn apply(name: &str, naming: &NamingConfig, is_dir: bool) -> String {
+    let (stem, extension) = split_name(name, is_dir);
+    let mut stem = stem.to_string();
+    let mut extension = extension.map(str::to_string);
+
+    if naming.rename_files {
+        stem = rename(&stem, naming);
+    }
+
+    if naming.sanitize_names {
+        stem = sanitize(&stem);
+        extension = extension.map(|e| sanitize(&e)).filter(|e| !e.is_empty());
+        if stem.is_empty() {
+            stem = EMPTY_NAME.to_string();
+        }
+        // "con.txt" is as invalid as "con"
+        if RESERVED_NAMES.contains(&stem.to_uppercase().as_str()) {
+            stem.push('_');
+        }
+    }
+
+    match extension {
+        Some(extension) => format!("{}.{}", stem, extension),
+        None => stem,
+    }
+}
+
+/// Split a name into stem and extension. Dot files like ".bashrc" have no extension.
+fn split_name(name: &str, is_dir: bool) -> (&str, Option<&str>) {
+    if is_dir {
+        return (name, None);
+    }
+    for extension in MULTI_PART_EXTENSIONS {
+        let suffix = extension.len() + 1;
+        if name.len() > suffix {
+            let split = name.len() - suffix;
+            let tail = &name.as_bytes()[split..];
+            if tail[0] == b'.' && tail[1..].eq_ignore_ascii_case(extension.as_bytes()) {
+                return (&name[..split], Some(&name[split + 1..]));
+            }
+        }
+    }
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name, None),
+    }
+}
+
+fn rename(stem: &str, naming: &NamingConfig) -> String {
+    match naming.rename_method {
+        RenameMethod::Lower => replace_spaces(stem, naming).to_lowercase(),
+        RenameMethod::Upper => replace_spaces(stem, naming).to_uppercase(),
+        RenameMethod::Title => title_case(&replace_spaces(stem, naming)),
+        // these styles bring their own separator
+        RenameMethod::Snake => join_words(stem, "_", |_, w| w.to_lowercase()),
+        RenameMethod::Kebab => join_words(stem, "-", |_, w| w.to_lowercase()),
+        RenameMethod::Camel => join_words(stem, "", |i, w| {
+            if i == 0 {
+                w.to_lowercase()
+            } else {
+                capitalize(w)
+            }
+        }),
+        RenameMethod::Pascal => join_words(stem, "", |_, w| capitalize(w)),
+    }
+}
+
+fn replace_spaces(stem: &str, naming: &NamingConfig) -> String {
+    stem.replace(' ', &naming.space_replacement)
+}
+
+/// Capitalize every run of letters and digits, e.g. "hello.world" → "Hello.World"
+fn title_case(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut word_start = true;
+    for c in s.chars() {
+        if c.is_alphanumeric() {
+            if word_start {
+                result.extend(c.to_uppercase());
+            } else {
+                result.extend(c.to_lowercase());
+            }
+            word_start = false;
+        } else {
+            result.push(c);
+            word_start = true;
+        }
+    }
+    result
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Split on anything that is not a letter or digit, and on camelCase boundaries,
+/// then join the transformed words with `separator`
+fn join_words(stem: &str, separator: &str, transform: impl Fn(usize, &str) -> String) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in stem.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    if words.is_empty() {
+        // nothing to restyle, e.g. a name made only of symbols
+        return stem.to_string();
+    }
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, w)| transform(i, w))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Strip invalid and control characters, and the trailing dots and spaces Windows drops
+fn sanitize(s: &str) -> String {
+    let cleaned: String = s
+        .chars()
+        .filter(|c| !INVALID_CHARS.contains(c) && !c.is_control())
+        .collect();
+    cleaned.trim_end_matches(['.', ' ']).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naming(rename_method: RenameMethod) -> NamingConfig {
+        NamingConfig {
+            rename_files: true,
+            rename_method,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rename_methods() {
+        let cases = [
+            (RenameMethod::Lower, "hello.world.txt"),
+            (RenameMethod::Upper, "HELLO.WORLD.txt"),
+            (RenameMethod::Title, "Hello.World.txt"),
+            (RenameMethod::Camel, "helloWorld.txt"),
+            (RenameMethod::Pascal, "HelloWorld.txt"),
+            (RenameMethod::Snake, "hello_world.txt"),
+            (RenameMethod::Kebab, "hello-world.txt"),
+        ];
+        for (method, expected) in cases {
+            assert_eq!(apply("Hello world.txt", &naming(method), false), expected);
+        }
+        assert_eq!(
+            apply("myReport_final v2.pdf", &naming(RenameMethod::Snake), false),
+            "my_report_final_v2.pdf"
+        );
+    }
+
+    #[test]
+    fn test_space_replacement() {
+        let naming = NamingConfig {
+            space_replacement: "-".to_string(),
+            ..naming(RenameMethod::Lower)
+        };
+        assert_eq!(apply("My File.TXT", &naming, false), "my-file.TXT");
+        // directories have no extension
+        assert_eq!(apply("Old Photos.2020", &naming, true), "old-photos.2020");
+    }
+
+    #[test]
+    fn test_sanitize_only_by_default() {
+        let naming = NamingConfig::default();
+        assert_eq!(apply("my:file?.txt", &naming, false), "myfile.txt");
+        assert_eq!(apply("Hello World.txt", &naming, false), "Hello World.txt");
+        assert_eq!(apply("report. .pdf", &naming, false), "report.pdf");
+        assert_eq!(apply("con.txt", &naming, false), "con_.txt");
+        assert_eq!(apply("???.txt", &naming, false), "_.txt");
+        assert_eq!(apply(".bashrc", &naming, false), ".bashrc");
+    }
+
+    #[test]
+    fn test_multi_part_extensions_stay_together() {
+        let naming = naming(RenameMethod::Kebab);
+        assert_eq!(
+            apply("My Archive.tar.gz", &naming, false),
+            "my-archive.tar.gz"
+        );
+        assert_eq!(apply("backup.TAR.XZ", &naming, false), "backup.TAR.XZ");
+        assert_eq!(apply("notes.v2.txt", &naming, false), "notes-v2.txt");
+    }
+
+    #[test]
+    fn test_no_sanitize() {
+        let naming = NamingConfig {
+            sanitize_names: false,
+            ..Default::default()
+        };
+        assert_eq!(apply("my:file?.txt", &naming, false), "my:file?.txt");
+    }
+}
@@ -0,0 +1,149 @@
+// This module carries over what `fs::copy` leaves behind when a move has to copy:
+// timestamps, ownership, extended attributes and permission bits
+use std::fs::{self, File, FileTimes, Metadata};
+use std::io;
+use std::path::Path;
+
+/// Give `dst` the metadata `src` had before it was copied. `src_meta` is read before the
+/// copy, as reading the source for the copy updates its access time.
+/// Returns what could not be preserved, as "what (error)" entries.
+pub fn preserve_metadata(src: &Path, dst: &Path, src_meta: &Metadata) -> Vec<String> {
+    let mut lost = Vec::new();
+
+    if let Err(e) = set_times(dst, src_meta) {
+        lost.push(format!("timestamps ({})", e));
+    }
+    #[cfg(unix)]
+    if let Err(e) = set_owner(dst, src_meta) {
+        lost.push(format!("ownership ({})", e));
+    }
+    #[cfg(target_os = "linux")]
+    lost.extend(copy_xattrs(src, dst));
+    #[cfg(not(target_os = "linux"))]
+    let _ = src;
+    // last, as read-only permissions could prevent the changes above
+    if let Err(e) = fs::set_permissions(dst, src_meta.permissions()) {
+        lost.push(format!("permissions ({})", e));
+    }
+
+    lost
+}
+
+fn set_times(path: &Path, src_meta: &Metadata) -> io::Result<()> {
+    let mut times = FileTimes::new().set_modified(src_meta.modified()?);
+    if let Ok(accessed) = src_meta.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    open_for_times(path, src_meta.is_dir())?.set_times(times)
+}
+
+#[cfg(not(windows))]
+fn open_for_times(path: &Path, _is_dir: bool) -> io::Result<File> {
+    // the owner may change the times through a read-only handle
+    File::open(path)
+}
+
+#[cfg(windows)]
+fn open_for_times(path: &Path, is_dir: bool) -> io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    // directories can only be opened with FILE_FLAG_BACKUP_SEMANTICS
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if is_dir {
+        options.custom_flags(FILE_FLAG_BACKUP_SEMANTICS);
+    }
+    options.open(path)
+}
+
+/// Only changed when the copy is owned by someone else, which takes privileges
+#[cfg(unix)]
+fn set_owner(path: &Path, src_meta: &Metadata) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let dst_meta = fs::metadata(path)?;
+    if dst_meta.uid() == src_meta.uid() && dst_meta.gid() == src_meta.gid() {
+        return Ok(());
+    }
+    std::os::unix::fs::chown(path, Some(src_meta.uid()), Some(src_meta.gid()))
+}
+
+#[cfg(target_os = "linux")]
+fn copy_xattrs(src: &Path, dst: &Path) -> Vec<String> {
+    let names = match xattr::list(src) {
+        Ok(names) => names,
+        // the filesystem has no extended attributes
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => return Vec::new(),
+        Err(e) => return vec![format!("extended attributes ({})", e)],
+    };
+    names
+        .filter_map(|name| {
+            let copied = match xattr::get(src, &name) {
+                Ok(Some(value)) => xattr::set(dst, &name, &value),
+                Ok(None) => Ok(()),
+                Err(e) => Err(e),
+            };
+            copied
+                .err()
+                .map(|e| format!("extended attribute {} ({})", name.to_string_lossy(), e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_times_and_permissions_are_preserved() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("photo.jpg");
+        let dst = dir.path().join("copy.jpg");
+        fs::write(&src, "jpg").unwrap();
+        let taken = SystemTime::now() - Duration::from_secs(3 * 365 * 24 * 3600);
+        File::options()
+            .write(true)
+            .open(&src)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(taken).set_accessed(taken))
+            .unwrap();
+        let mut permissions = fs::metadata(&src).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&src, permissions).unwrap();
+
+        let src_meta = fs::metadata(&src).unwrap();
+        fs::write(&dst, "jpg").unwrap();
+        assert!(preserve_metadata(&src, &dst, &src_meta).is_empty());
+
+        let dst_meta = fs::metadata(&dst).unwrap();
+        assert_eq!(dst_meta.modified().unwrap(), taken);
+        assert_eq!(dst_meta.accessed().unwrap(), taken);
+        assert!(dst_meta.permissions().readonly());
+    }
+
+    #[test]
+    fn test_directory_times_are_preserved() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("album");
+        let dst = dir.path().join("copy");
+        fs::create_dir(&src).unwrap();
+        fs::create_dir(&dst).unwrap();
+        let src_meta = fs::metadata(&src).unwrap();
+        let before = src_meta.modified().unwrap();
+
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(dst.join("photo.jpg"), "jpg").unwrap();
+        assert!(preserve_metadata(&src, &dst, &src_meta).is_empty());
+        assert_eq!(fs::metadata(&dst).unwrap().modified().unwrap(), before);
+    }
+
+    #[test]
+    fn test_missing_copy_is_reported() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("notes.txt");
+        fs::write(&src, "notes").unwrap();
+        let src_meta = fs::metadata(&src).unwrap();
+        assert!(!preserve_metadata(&src, &dir.path().join("missing.txt"), &src_meta).is_empty());
+    }
+}
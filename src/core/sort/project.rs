@@ -0,0 +1,56 @@
+// This module recognizes project folders (code repositories, IDE workspaces) that must
+// never be taken apart by a sort
+use std::fs;
+use std::path::Path;
+
+use crate::config::config_processor::GeneralConfig;
+
+/// Markers used when [general] project_markers is not set
+pub const DEFAULT_PROJECT_MARKERS: &[&str] =
+    &[".git", "Cargo.toml", "package.json", ".idea", ".vscode"];
+
+/// The configured markers, or the default ones
+pub fn project_markers(general: &GeneralConfig) -> Vec<&str> {
+    match &general.project_markers {
+        Some(markers) => markers.iter().map(String::as_str).collect(),
+        None => DEFAULT_PROJECT_MARKERS.to_vec(),
+    }
+}
+
+/// Return the first marker found directly inside `dir`, if it is a project folder
+pub fn project_marker<'a>(dir: &Path, markers: &[&'a str]) -> Option<&'a str> {
+    markers
+        .iter()
+        // the marker itself may be a broken symlink, it still marks the folder
+        .find(|marker| fs::symlink_metadata(dir.join(marker)).is_ok())
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_project_marker() {
+        let dir = tempdir().unwrap();
+        assert_eq!(project_marker(dir.path(), DEFAULT_PROJECT_MARKERS), None);
+
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        assert_eq!(
+            project_marker(dir.path(), DEFAULT_PROJECT_MARKERS),
+            Some(".git")
+        );
+        // an empty list turns the protection off
+        assert_eq!(project_marker(dir.path(), &[]), None);
+    }
+
+    #[test]
+    fn test_configured_markers() {
+        let mut general = GeneralConfig::default();
+        assert_eq!(project_markers(&general), DEFAULT_PROJECT_MARKERS);
+
+        general.project_markers = Some(vec!["go.mod".to_string()]);
+        assert_eq!(project_markers(&general), vec!["go.mod"]);
+    }
+}
@@ -0,0 +1,206 @@
+// This module decides which paths iris must never sort nor move files into: system
+// folders, the home directory and its standard folders, and the configured protected_paths
+use std::path::{Path, PathBuf};
+
+use crate::config::config_processor::{GeneralConfig, ProtectedPath, ProtectionScope};
+
+use ProtectionScope::{Exact, Subtree};
+
+#[cfg(target_os = "windows")]
+const PROTECTED_PATHS: &[(&str, ProtectionScope)] = &[
+    ("C:\\", Exact),
+    ("C:\\Windows", Subtree),
+    ("C:\\Program Files", Subtree),
+    ("C:\\Program Files (x86)", Subtree),
+    // the profiles below are sorted, not the folder holding them
+    ("C:\\Users", Exact),
+    ("C:\\Users\\Administrator", Exact),
+    ("C:\\ProgramData", Subtree),
+    ("C:\\System32", Subtree),
+    ("C:\\Recovery", Subtree),
+    ("C:\\PerfLogs", Subtree),
+];
+
+#[cfg(target_os = "linux")]
+const PROTECTED_PATHS: &[(&str, ProtectionScope)] = &[
+    ("/", Exact),
+    ("/bin", Subtree),
+    ("/boot", Subtree),
+    ("/dev", Subtree),
+    ("/etc", Subtree),
+    ("/home", Exact),
+    ("/lib", Subtree),
+    ("/lib32", Subtree),
+    ("/lib64", Subtree),
+    ("/libx32", Subtree),
+    // removable drives are mounted below these, and may be sorted
+    ("/media", Exact),
+    ("/mnt", Exact),
+    ("/run", Exact),
+    ("/opt", Subtree),
+    ("/proc", Subtree),
+    ("/root", Exact),
+    ("/sbin", Subtree),
+    ("/srv", Exact),
+    ("/sys", Subtree),
+    ("/usr", Subtree),
+    ("/var", Subtree),
+];
+
+#[cfg(target_os = "android")]
+const PROTECTED_PATHS: &[(&str, ProtectionScope)] = &[
+    ("/system", Subtree),
+    ("/vendor", Subtree),
+    ("/proc", Subtree),
+    ("/sys", Subtree),
+];
+
+#[cfg(target_os = "macos")]
+const PROTECTED_PATHS: &[(&str, ProtectionScope)] = &[
+    ("/", Exact),
+    ("/System", Subtree),
+    ("/bin", Subtree),
+    ("/sbin", Subtree),
+    ("/usr", Subtree),
+    // temporary folders live below these
+    ("/private", Exact),
+    ("/var", Exact),
+    ("/etc", Subtree),
+    ("/dev", Subtree),
+    ("/Applications", Subtree),
+    ("/Library", Subtree),
+    ("/Users", Exact),
+];
+
+/// Every protected path of a run: the built-in ones, the home directory, the standard
+/// folders of the user and the configured protected_paths
+#[derive(Debug, Clone, Default)]
+pub struct ProtectedPaths {
+    paths: Vec<ProtectedPath>,
+}
+
+impl ProtectedPaths {
+    pub fn new(general: &GeneralConfig) -> Self {
+        let built_in = PROTECTED_PATHS.iter().map(|(path, scope)| ProtectedPath {
+            path: PathBuf::from(path),
+            scope: *scope,
+        });
+        let paths = built_in
+            .chain(user_paths())
+            .chain(general.protected_paths.iter().cloned())
+            // targets are canonicalized, so compare against the resolved paths
+            .map(|p| ProtectedPath {
+                path: dunce::canonicalize(&p.path).unwrap_or(p.path),
+                scope: p.scope,
+            })
+            .collect();
+        ProtectedPaths { paths }
+    }
+
+    /// The protected path covering `path`, if any
+    pub fn matching(&self, path: &Path) -> Option<&ProtectedPath> {
+        self.paths.iter().find(|p| match p.scope {
+            Exact => path == p.path,
+            Subtree => path.starts_with(&p.path),
+        })
+    }
+
+    /// The protected path that moving the folder `dir` would take along, if any
+    pub fn inside(&self, dir: &Path) -> Option<&ProtectedPath> {
+        self.paths.iter().find(|p| p.path.starts_with(dir))
+    }
+}
+
+/// The home directory itself, and the folders applications keep their files in.
+/// Folders like Downloads or Pictures are meant to be sorted and are not included.
+fn user_paths() -> Vec<ProtectedPath> {
+    let exact = dirs::home_dir().map(|path| ProtectedPath { path, scope: Exact });
+    // on Windows the temporary folder lives below the local data folder
+    let local_scope = if cfg!(windows) { Exact } else { Subtree };
+    let local = [dirs::data_local_dir(), dirs::cache_dir()]
+        .into_iter()
+        .flatten()
+        .map(|path| ProtectedPath {
+            path,
+            scope: local_scope,
+        });
+    let subtree = [
+        dirs::config_dir(),
+        dirs::data_dir(),
+        dirs::state_dir(),
+        dirs::executable_dir(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|path| ProtectedPath {
+        path,
+        scope: Subtree,
+    });
+    exact.into_iter().chain(local).chain(subtree).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn protected(paths: Vec<ProtectedPath>) -> ProtectedPaths {
+        let general = GeneralConfig {
+            protected_paths: paths,
+            ..Default::default()
+        };
+        ProtectedPaths::new(&general)
+    }
+
+    #[test]
+    fn test_home_and_user_folders() {
+        let protected = protected(Vec::new());
+        if let Some(home) = dirs::home_dir() {
+            let home = dunce::canonicalize(&home).unwrap_or(home);
+            assert!(protected.matching(&home).is_some());
+            // only the home directory itself
+            assert!(protected.matching(&home.join("Downloads")).is_none());
+        }
+        if let Some(config) = dirs::config_dir() {
+            let config = dunce::canonicalize(&config).unwrap_or(config);
+            assert!(protected.matching(&config.join("iris")).is_some());
+        }
+    }
+
+    #[test]
+    fn test_configured_scopes() {
+        let dir = tempdir().unwrap();
+        let root = dunce::canonicalize(dir.path()).unwrap();
+        let protected = protected(vec![
+            ProtectedPath {
+                path: root.join("archive"),
+                scope: Exact,
+            },
+            ProtectedPath {
+                path: root.join("vault"),
+                scope: Subtree,
+            },
+        ]);
+
+        assert!(protected.matching(&root.join("archive")).is_some());
+        assert!(protected.matching(&root.join("archive/2020")).is_none());
+        assert!(protected.matching(&root.join("vault")).is_some());
+        assert!(protected.matching(&root.join("vault/keys")).is_some());
+        assert!(protected.matching(&root).is_none());
+
+        // moving the parent would move the protected folders too
+        assert!(protected.inside(&root).is_some());
+        assert!(protected.inside(&root.join("other")).is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_system_paths() {
+        let protected = protected(Vec::new());
+        assert!(protected.matching(Path::new("/")).is_some());
+        assert!(protected.matching(Path::new("/usr/share")).is_some());
+        assert!(protected.matching(Path::new("/mnt")).is_some());
+        assert!(protected.matching(Path::new("/mnt/usb")).is_none());
+        assert!(protected.matching(Path::new("/tmp/downloads")).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+// This module reports the outcome of a sort run to scripts: `--output json` prints one
+// document once the run is over, `--output ndjson` one object per line as moves complete
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::core::sort::sort::MoveMethod;
+
+/// How `iris sort` reports what it did
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Coloured text for humans
+    #[default]
+    Text,
+    /// A single JSON document once the run is over
+    Json,
+    /// One JSON object per line as moves complete, then the summary
+    Ndjson,
+}
+
+/// Outcome of a single planned move
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MoveReport {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub preset: String,
+    // `None` when the move failed or during a dry run
+    pub method: Option<MoveMethod>,
+    pub bytes: u64,
+    pub error: Option<String>,
+}
+
+/// An entry left in place during planning
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkipReport {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// Totals of a sort run, returned by `sort` and printed last
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SortSummary {
+    pub moved: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    // size of the entries moved, or that would be moved during a dry run
+    pub bytes: u64,
+    pub dry_run: bool,
+    // id to pass to `iris undo`, `None` when no journal was written
+    pub run_id: Option<String>,
+}
+
+/// A line of `--output ndjson`
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'a> {
+    Move(&'a MoveReport),
+    Skipped(&'a SkipReport),
+    Summary(&'a SortSummary),
+}
+
+/// The document printed by `--output json`
+#[derive(Serialize)]
+struct Report<'a> {
+    moves: &'a [MoveReport],
+    skipped: &'a [SkipReport],
+    summary: &'a SortSummary,
+}
+
+/// Print a move as soon as it is done, for `--output ndjson`
+pub fn emit_move(format: OutputFormat, report: &MoveReport) {
+    if format == OutputFormat::Ndjson {
+        print_line(&Record::Move(report));
+    }
+}
+
+/// Print the end of the report: the skipped entries and the summary for `--output ndjson`,
+/// the whole document for `--output json`
+pub fn finish(
+    format: OutputFormat,
+    moves: &[MoveReport],
+    skipped: &[SkipReport],
+    summary: &SortSummary,
+) {
+    match format {
+        OutputFormat::Text => {}
+        OutputFormat::Json => {
+            let report = Report {
+                moves,
+                skipped,
+                summary,
+            };
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("Failed to serialize the sort report: {}", e),
+            }
+        }
+        OutputFormat::Ndjson => {
+            for skip in skipped {
+                print_line(&Record::Skipped(skip));
+            }
+            print_line(&Record::Summary(summary));
+        }
+    }
+}
+
+fn print_line(record: &Record) {
+    match serde_json::to_string(record) {
+        // a single println keeps lines whole when moves finish on several threads
+        Ok(line) => println!("{}", line),
+        Err(e) => eprintln!("Failed to serialize the sort report: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ndjson_records_are_tagged() {
+        let report = MoveReport {
+            source: PathBuf::from("/in/notes.txt"),
+            destination: PathBuf::from("/in/documents/notes.txt"),
+            preset: "documents".to_string(),
+            method: Some(MoveMethod::Rename),
+            bytes: 5,
+            error: None,
+        };
+        let line = serde_json::to_value(Record::Move(&report)).unwrap();
+        assert_eq!(line["type"], "move");
+        assert_eq!(line["method"], "rename");
+        assert_eq!(line["bytes"], 5);
+        assert!(line["error"].is_null());
+
+        let summary = SortSummary {
+            moved: 1,
+            ..Default::default()
+        };
+        let line = serde_json::to_value(Record::Summary(&summary)).unwrap();
+        assert_eq!(line["type"], "summary");
+        assert_eq!(line["moved"], 1);
+        assert!(line["run_id"].is_null());
+    }
+}
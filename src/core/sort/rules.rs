@@ -0,0 +1,214 @@
+// This module evaluates the [[preset.X.rule]] blocks of a preset against a file
+use regex::{Regex, RegexBuilder};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::config::config_processor::{PresetConfig, PresetRule};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A rule with its regex compiled once for the whole run
+#[derive(Debug)]
+struct CompiledRule<'a> {
+    rule: &'a PresetRule,
+    regex: Option<Regex>,
+}
+
+/// The rules of a single preset, in the order they are tried
+#[derive(Debug, Default)]
+pub struct PresetRules<'a> {
+    rules: Vec<CompiledRule<'a>>,
+}
+
+impl<'a> PresetRules<'a> {
+    pub fn new(preset: &'a PresetConfig) -> Result<Self, String> {
+        let rules = preset
+            .rules
+            .iter()
+            .map(|rule| {
+                let regex = rule
+                    .filename_regex
+                    .as_deref()
+                    .map(|expr| {
+                        RegexBuilder::new(expr)
+                            .case_insensitive(!rule.match_case)
+                            .build()
+                            .map_err(|e| {
+                                format!(
+                                    "[preset.{}] invalid filename_regex '{}': {}",
+                                    preset.name, expr, e
+                                )
+                            })
+                    })
+                    .transpose()?;
+                Ok(CompiledRule { rule, regex })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(PresetRules { rules })
+    }
+
+    /// Subfolder of the preset destination given by the first rule matching `path`.
+    /// `None` means the file goes to the preset root.
+    pub fn folder_for(&self, path: &Path) -> Option<&'a Path> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let name = path.file_name()?.to_string_lossy();
+        self.rules
+            .iter()
+            .find(|compiled| compiled.matches(path, &name))
+            .map(|compiled| compiled.rule.folder.as_path())
+    }
+}
+
+impl CompiledRule<'_> {
+    fn matches(&self, path: &Path, name: &str) -> bool {
+        let rule = self.rule;
+
+        if !rule.extension.is_empty() {
+            let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+            if !extension.is_some_and(|e| rule.extension.contains(&e)) {
+                return false;
+            }
+        }
+
+        if let Some(needle) = &rule.filename_contains {
+            let found = if rule.match_case {
+                name.contains(needle.as_str())
+            } else {
+                name.to_lowercase().contains(&needle.to_lowercase())
+            };
+            if !found {
+                return false;
+            }
+        }
+
+        if let Some(regex) = &self.regex {
+            if !regex.is_match(name) {
+                return false;
+            }
+        }
+
+        if !rule.size.is_unbounded() {
+            let fits = fs::metadata(path)
+                .map(|m| rule.size.allows(m.len()))
+                .unwrap_or(false);
+            if !fits {
+                return false;
+            }
+        }
+
+        if let Some(days) = rule.modified_within_days {
+            let max_age = Duration::from_secs(days.saturating_mul(SECONDS_PER_DAY));
+            let recent = fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                // a modification time in the future counts as recent
+                .map(|t| SystemTime::now().duration_since(t).unwrap_or_default() <= max_age)
+                .unwrap_or(false);
+            if !recent {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_processor::SizeLimits;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    fn preset(rules: Vec<PresetRule>) -> PresetConfig {
+        PresetConfig {
+            name: "documents".to_string(),
+            rules,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let preset = preset(vec![
+            PresetRule {
+                filename_contains: Some("invoice".to_string()),
+                folder: PathBuf::from("invoices"),
+                ..Default::default()
+            },
+            PresetRule {
+                extension: vec!["pdf".to_string()],
+                filename_regex: Some(r"report_[0-9]{4}".to_string()),
+                folder: PathBuf::from("pdf/reports"),
+                ..Default::default()
+            },
+        ]);
+        let rules = PresetRules::new(&preset).unwrap();
+
+        assert_eq!(
+            rules.folder_for(Path::new("/t/INVOICE_report_2024.pdf")),
+            Some(Path::new("invoices"))
+        );
+        assert_eq!(
+            rules.folder_for(Path::new("/t/report_2024.pdf")),
+            Some(Path::new("pdf/reports"))
+        );
+        // every condition of a rule has to hold
+        assert_eq!(rules.folder_for(Path::new("/t/report_2024.txt")), None);
+        assert_eq!(rules.folder_for(Path::new("/t/notes.pdf")), None);
+    }
+
+    #[test]
+    fn test_match_case() {
+        let preset = preset(vec![PresetRule {
+            filename_contains: Some("invoice".to_string()),
+            match_case: true,
+            folder: PathBuf::from("invoices"),
+            ..Default::default()
+        }]);
+        let rules = PresetRules::new(&preset).unwrap();
+        assert_eq!(rules.folder_for(Path::new("/t/INVOICE.pdf")), None);
+        assert!(rules.folder_for(Path::new("/t/invoice.pdf")).is_some());
+    }
+
+    #[test]
+    fn test_modified_within_days() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("recent.pdf");
+        fs::write(&file, "pdf").unwrap();
+
+        let preset = preset(vec![PresetRule {
+            modified_within_days: Some(7),
+            folder: PathBuf::from("recent"),
+            ..Default::default()
+        }]);
+        let rules = PresetRules::new(&preset).unwrap();
+        assert_eq!(rules.folder_for(&file), Some(Path::new("recent")));
+        // a file that cannot be inspected is never recent
+        assert_eq!(rules.folder_for(&dir.path().join("missing.pdf")), None);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let dir = tempdir().unwrap();
+        let small = dir.path().join("small.mp4");
+        let large = dir.path().join("large.mp4");
+        fs::write(&small, vec![0u8; 10]).unwrap();
+        fs::write(&large, vec![0u8; 2048]).unwrap();
+
+        let preset = preset(vec![PresetRule {
+            size: SizeLimits {
+                min: Some(1024),
+                max: None,
+            },
+            folder: PathBuf::from("large"),
+            ..Default::default()
+        }]);
+        let rules = PresetRules::new(&preset).unwrap();
+        assert_eq!(rules.folder_for(&large), Some(Path::new("large")));
+        assert_eq!(rules.folder_for(&small), None);
+    }
+}
@@ -1,13 +1,8 @@
-// TODO: Implement dry-run (simulation) before actual file operations. provide support for  --dry-run (alias = [ --sim | --simulate ]) flag
-// TODO: Implement undo feature as a safe revert option incase something which should'nt be sorted gets sorted
 // TODO: Break this into smaller modules and functions and make this the entry point for the sort command
 // TODO: Write unit tests for this module
 // TODO: Skip hashing for small files (less than 1MB) or when rename succeeds
-// TODO: in the copy fallback add a fast-verify mode: first compare size; if equal then hash only a small head + tail chunk for large files. then full hash it if that mismatches
-// TODO: add a concurrency limit (thread pool size) configurable through config file as well as cli override
-// TODO: Update PROTECTED_PATHS with USER's home directory on all platforms
 use colored::Colorize;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::path::PathBuf;
 use std::{fs, io};
@@ -17,77 +12,158 @@ use walkdir::WalkDir;
 // parallel moves
 use rayon::prelude::*;
 
-use crate::config::config_processor::{IrisConfig, Mode, PresetConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_processor::{
+    IrisConfig, Mode, NamingConfig, PresetConfig, SymlinkPolicy, CATCH_ALL_EXTENSION,
+};
+use crate::core::journal::{JournalRecord, JournalWriter, MoveEntry};
 use crate::core::resolver::dest_base_resolver;
+use crate::core::resolver::dest_template::FolderMatcher;
+use crate::core::sort::conflict::{resolve_destination, Resolution, DEFAULT_CONFLICT_PATTERN};
+use crate::core::sort::content;
+use crate::core::sort::copy::{copy_verified, warn_not_preserved, CopyOptions};
+use crate::core::sort::exclusions::{is_excluded_directory, ExclusionSet};
+use crate::core::sort::hidden::is_hidden;
+use crate::core::sort::in_progress::{self, is_in_progress_name};
+use crate::core::sort::naming;
+use crate::core::sort::preserve::preserve_metadata;
+use crate::core::sort::project::{project_marker, project_markers};
+use crate::core::sort::protected::ProtectedPaths;
+use crate::core::sort::report::{self, MoveReport, OutputFormat, SkipReport, SortSummary};
+use crate::core::sort::rules::PresetRules;
+use crate::core::sort::symlinks::{copy_link, dangling_target, move_link, MovedPaths};
+use crate::core::sort::throttle::Throttle;
+use crate::core::sort::walk::{preset_max_depth, walk_depth};
+use crate::logging;
+
+/// Runtime options for a single sort invocation (usually coming from CLI flags)
+#[derive(Debug, Clone, Default)]
+pub struct SortOptions {
+    /// plan the moves and print them without touching the disk
+    pub dry_run: bool,
+    /// directory to write the run journal to. no journal is written when `None`
+    pub journal_dir: Option<PathBuf>,
+    /// also list the entries left alone because of an exclusion
+    pub verbose: bool,
+    /// only sort these entries of the target (used by `iris watch`), `None` sorts everything
+    pub only: Option<HashSet<PathBuf>>,
+    /// sort project folders (containing .git, Cargo.toml...) like any other folder
+    pub force: bool,
+    /// sort and sort into protected paths (--i-know-what-im-doing)
+    pub allow_protected: bool,
+    /// human text, or JSON for scripts
+    pub output: OutputFormat,
+    /// moves running at the same time (--jobs), overrides `threads` of the config
+    pub jobs: Option<usize>,
+}
+
+/// A move decided during the planning phase
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlannedMove {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub preset: String,
+    pub kind: MoveKind,
+}
+
+/// What a planned move does at its destination
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MoveKind {
+    #[default]
+    Move,
+    // replace the existing destination file
+    Overwrite,
+    // the destination already holds identical content, only the source is removed
+    Dedupe,
+}
 
-#[cfg(target_os = "windows")]
-const PROTECTED_PATHS: &[&str] = &[
-    "C:\\",
-    "C:\\Windows",
-    "C:\\Program Files",
-    "C:\\Program Files (x86)",
-    "C:\\Users",
-    "C:\\Users\\Administrator",
-    "C:\\ProgramData",
-    "C:\\System32",
-    "C:\\Windows\\System32",
-    "C:\\Recovery",
-    "C:\\PerfLogs",
-];
-
-#[cfg(target_os = "linux")]
-const PROTECTED_PATHS: &[&str] = &[
-    "/", "/bin", "/boot", "/dev", "/etc", "/lib", "/lib32", "/lib64", "/libx32", "/media", "/mnt",
-    "/opt", "/proc", "/root", "/run", "/sbin", "/srv", "/sys", "/usr", "/var",
-];
-
-#[cfg(target_os = "android")]
-const PROTECTED_PATHS: &[&str] = &["/system", "/vendor", "/proc", "/sys"];
-
-#[cfg(target_os = "macos")]
-const PROTECTED_PATHS: &[&str] = &[
-    "/",
-    "/System",
-    "/bin",
-    "/sbin",
-    "/usr",
-    "/private",
-    "/var",
-    "/etc",
-    "/dev",
-    "/Applications",
-    "/Library",
-];
-
-/// Move files from target -> preset-driven destinations safely
-pub fn sort(target: &Path, config: &IrisConfig) -> Result<(), Box<dyn std::error::Error>> {
+/// How a file or directory ended up at its destination
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveMethod {
+    // atomic rename on the same filesystem
+    Rename,
+    // cross-filesystem copy followed by deleting the source
+    Copy,
+    // source removed because an identical file already existed at the destination
+    Dedupe,
+    // symbolic link recreated at the destination, pointing to the same place
+    Relink,
+}
+
+/// Move files from target -> preset-driven destinations safely.
+/// Failed moves do not make the run fail, they are counted in the returned summary.
+pub fn sort(
+    target: &Path,
+    config: &IrisConfig,
+    options: &SortOptions,
+) -> Result<SortSummary, Box<dyn std::error::Error>> {
     // target is assumed cleaned/canonicalized by resolver
     let target = target.to_path_buf();
 
-    // fail-safe: only block if target exactly matches a protected system path
-    for p in PROTECTED_PATHS {
-        let protected = Path::new(p);
-        if target == protected {
-            return Err(format!(
-                "Operation aborted. '{}' is a protected system path.",
-                target.display()
-            )
-            .into());
-        }
+    // fail-safe: system folders, the home directory and the configured protected paths
+    let protected = if options.allow_protected {
+        ProtectedPaths::default()
+    } else {
+        ProtectedPaths::new(&config.general)
+    };
+    if let Some(p) = protected.matching(&target) {
+        let reason = if p.path == target {
+            "is a protected path".to_string()
+        } else {
+            format!("is inside the protected path '{}'", p.path.display())
+        };
+        return Err(format!(
+            "Operation aborted. '{}' {}. Use --i-know-what-im-doing to sort it anyway.",
+            target.display(),
+            reason
+        )
+        .into());
     }
 
-    println!("Sorting files in: {}", target.display());
+    // a project folder would be taken apart: its files sorted away from each other
+    let markers = if options.force {
+        Vec::new()
+    } else {
+        project_markers(&config.general)
+    };
+    if let Some(marker) = project_marker(&target, &markers) {
+        return Err(format!(
+            "Operation aborted. '{}' looks like a project folder (contains {}). \
+             Use --force to sort it anyway.",
+            target.display(),
+            marker
+        )
+        .into());
+    }
+
+    // human messages would break the JSON on stdout, and -q hides progress messages
+    let text = options.output == OutputFormat::Text;
+    let progress = text && !logging::quiet();
+    if options.only.is_none() && progress {
+        println!("Sorting files in: {}", target.display());
+    }
 
     let mode: &Mode = &config.general.mode;
 
-    // pre-build a HashMap for efficient extension-to-preset lookups.
-    // the first preset encountered for a given extension takes precedence.
-    let mut ext_map: HashMap<String, &PresetConfig> = HashMap::new();
-    for preset in config.presets.iter().filter(|p| p.enabled) {
-        for ext in &preset.extension {
-            ext_map.entry(ext.to_lowercase()).or_insert(preset);
-        }
-    }
+    // extension-to-preset lookups. presets come in precedence order (priority, then
+    // file order), so the first preset claiming an extension takes precedence.
+    // later presets only get a file that is outside the size limits of the earlier ones.
+    let ext_map = config.extension_claims();
+
+    // presets collecting what no extension matched: files with an unknown extension
+    // go to the "*" presets, files without any extension to the extensionless ones
+    let catch_all_presets: Vec<&PresetConfig> = config
+        .presets
+        .iter()
+        .filter(|p| p.enabled && p.extension.iter().any(|e| e == CATCH_ALL_EXTENSION))
+        .collect();
+    let extensionless_presets: Vec<&PresetConfig> = config
+        .presets
+        .iter()
+        .filter(|p| p.enabled && p.extensionless)
+        .collect();
 
     // find the "dirs" preset
     let dirs_preset = config
@@ -95,7 +171,7 @@ pub fn sort(target: &Path, config: &IrisConfig) -> Result<(), Box<dyn std::error
         .iter()
         .find(|p| p.name == "dirs" && p.enabled);
 
-    if dirs_preset.is_some() {
+    if dirs_preset.is_some() && options.only.is_none() && progress {
         println!("{}", "Folder sorting enabled.".bright_green());
     }
 
@@ -106,11 +182,9 @@ pub fn sort(target: &Path, config: &IrisConfig) -> Result<(), Box<dyn std::error
         for preset in config.presets.iter().filter(|p| p.enabled) {
             if let Some(rel_path) = &preset.relative_path {
                 // get the first component of the relative path
-                if let Some(first_comp) = rel_path.components().next() {
-                    if let std::path::Component::Normal(c) = first_comp {
-                        if let Some(s) = c.to_str() {
-                            protected_names.insert(s.to_lowercase());
-                        }
+                if let Some(std::path::Component::Normal(c)) = rel_path.components().next() {
+                    if let Some(s) = c.to_str() {
+                        protected_names.insert(s.to_lowercase());
                     }
                 }
             }
@@ -118,322 +192,1053 @@ pub fn sort(target: &Path, config: &IrisConfig) -> Result<(), Box<dyn std::error
     }
 
     // phase 1: walk and plan moves (single-threaded)
-    let mut planned_moves: Vec<(PathBuf, PathBuf)> = Vec::new();
-    let mut reserved_dests: HashSet<PathBuf> = HashSet::new();
+    let mut planned_moves: Vec<PlannedMove> = Vec::new();
+    let mut skipped: Vec<(PathBuf, String)> = Vec::new();
+    let mut reserved_dests: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+    // output folders of every preset are never descended into, so sorted files stay put
+    let output_dirs = output_dirs(&target, config);
+
+    // compile the global exclusions and each preset's own exclude list once
+    let case_sensitive = config.exclusions.case_sensitive;
+    let exclusions = ExclusionSet::new(&config.exclusions.patterns, case_sensitive)?;
+    let mut preset_exclusions: HashMap<&str, ExclusionSet> = HashMap::new();
+    for preset in config.presets.iter().filter(|p| p.enabled) {
+        let set = ExclusionSet::new(&preset.exclude, case_sensitive)
+            .map_err(|e| format!("[preset.{}] exclude: {}", preset.name, e))?;
+        preset_exclusions.insert(preset.name.as_str(), set);
+    }
+    let mut preset_rules: HashMap<&str, PresetRules> = HashMap::new();
+    for preset in config.presets.iter().filter(|p| p.enabled) {
+        preset_rules.insert(preset.name.as_str(), PresetRules::new(preset)?);
+    }
+    let preset_naming: HashMap<&str, NamingConfig> = config
+        .presets
+        .iter()
+        .filter(|p| p.enabled)
+        .map(|p| (p.name.as_str(), config.naming.with_overrides(&p.naming)))
+        .collect();
+    let mut excluded: Vec<(PathBuf, String)> = Vec::new();
+    // files open in other processes are listed once for the whole run
+    let open_files = if config.exclusions.skip_open_files {
+        in_progress::open_files()
+    } else {
+        HashSet::new()
+    };
+
+    // hidden folders are only entered when a preset may sort what they hold
+    let include_hidden = |preset: &PresetConfig| {
+        preset
+            .include_hidden
+            .unwrap_or(config.general.include_hidden)
+    };
+    let any_include_hidden = config
+        .presets
+        .iter()
+        .any(|p| p.enabled && include_hidden(p));
+    let mut hidden_dirs: Vec<PathBuf> = Vec::new();
 
-    for entry in WalkDir::new(target.clone()).min_depth(1).max_depth(1) {
+    let max_depth = walk_depth(config);
+    let mut walker = WalkDir::new(target.clone())
+        .min_depth(1)
+        .max_depth(max_depth)
+        .into_iter();
+
+    while let Some(entry) = walker.next() {
         let entry = match entry {
             Ok(e) => e,
             Err(e) => {
-                eprintln!("Error walking directory: {}", e);
+                log::warn!("Error walking directory: {}", e);
                 continue;
             }
         };
 
         let path = entry.path();
-        let is_dir = entry.file_type().is_dir();
-
-        // handle directories
-        if is_dir {
-            if let Some(preset) = dirs_preset {
-                // check if this directory is a protected preset output folder
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if protected_names.contains(&name.to_lowercase()) {
-                        // it matches a preset path, so skip it
-                        continue;
-                    }
-                }
+        // links are sorted like what they point to, but linked folders are never entered
+        let is_link = entry.path_is_symlink();
+        let link_meta = if is_link {
+            fs::metadata(path).ok()
+        } else {
+            None
+        };
+        let is_dir = match &link_meta {
+            Some(meta) => meta.is_dir(),
+            None => entry.file_type().is_dir(),
+        };
+        // directories are still walked when not selected, files inside them may be
+        let selected = options.only.as_ref().is_none_or(|only| only.contains(path));
+        if !is_dir && !selected {
+            continue;
+        }
+        let top_level = entry.depth() == 1;
+        // directories at the depth limit are never entered, and skipping one there
+        // would skip the rest of its parent instead
+        let descends = is_dir && !is_link && entry.depth() < max_depth;
 
-                // resolve the destination base path for the preset
-                match dest_base_resolver::get_dest_base(&target, preset, mode.clone()) {
-                    Ok(dest_base) => {
-                        // guard: destination base should not be a dangerous system path
-                        if PROTECTED_PATHS.iter().any(|p| Path::new(p) == dest_base) {
-                            eprintln!(
-                                "Refusing to sort into protected path: {}",
-                                dest_base.display()
-                            );
-                            continue;
-                        }
+        // excluded entries are never moved, and excluded directories never descended into
+        if let Some(pattern) = exclusions.matching(&target, path) {
+            if selected {
+                excluded.push((
+                    path.to_path_buf(),
+                    format!("excluded by pattern {}", pattern),
+                ));
+            }
+            if descends {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
 
-                        // skip if the directory IS the destination folder
-                        if path == dest_base {
-                            continue;
-                        }
+        // unfinished downloads are never moved, whatever the config
+        if is_in_progress_name(path) {
+            if selected {
+                excluded.push((path.to_path_buf(), "download in progress".to_string()));
+            }
+            if descends {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
 
-                        // compute destination folder path
-                        let folder_name = match path.file_name() {
-                            Some(n) => n.to_owned(),
-                            None => continue,
-                        };
-                        let desired = dest_base.join(folder_name);
-                        let dest_path = reserve_unique_destination(&desired, &mut reserved_dests);
-
-                        // if source and destination are identical, skip
-                        if path == dest_path {
-                            continue;
-                        }
+        // protected paths below the target, or folders holding one, stay where they are
+        let guarded = protected
+            .matching(path)
+            .or_else(|| protected.inside(path).filter(|_| is_dir));
+        if let Some(p) = guarded {
+            if selected {
+                skipped.push((
+                    path.to_path_buf(),
+                    format!("protected path {}", p.path.display()),
+                ));
+            }
+            if descends {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
 
-                        planned_moves.push((path.to_path_buf(), dest_path));
-                    }
-                    Err(e) => eprintln!(
-                        "Could not determine sort destination for folder '{}': {}",
-                        path.display(),
-                        e
-                    ),
+        // hidden entries, and whatever hidden folders hold, are only sorted by the
+        // presets including them
+        let hidden = hidden_dirs.iter().any(|d| path.starts_with(d))
+            || is_hidden(path, entry.metadata().ok().as_ref());
+        if hidden && descends {
+            if !any_include_hidden {
+                if selected {
+                    excluded.push((path.to_path_buf(), "hidden folder".to_string()));
                 }
+                walker.skip_current_dir();
+                continue;
             }
-            continue;
+            hidden_dirs.push(path.to_path_buf());
         }
 
-        // handle files
-        // get the extension of the file (lowercased)
-        let extension = match path.extension().and_then(|s| s.to_str()) {
-            Some(ext) => ext.to_lowercase(),
-            None => continue, // skip files without an extension
-        };
+        if is_link {
+            if !selected {
+                continue;
+            }
+            if let Some(link_target) = dangling_target(path) {
+                skipped.push((
+                    path.to_path_buf(),
+                    format!("dangling symbolic link to {}", link_target.display()),
+                ));
+                continue;
+            }
+            if config.general.symlinks == SymlinkPolicy::Skip {
+                excluded.push((path.to_path_buf(), "symbolic link".to_string()));
+                continue;
+            }
+        }
 
-        // get the preset for the extension
-        if let Some(preset) = ext_map.get(&extension) {
-            // resolve the destination base path for the preset
-            match dest_base_resolver::get_dest_base(&target, preset, mode.clone()) {
-                Ok(dest_base) => {
-                    // guard: destination base should not be a dangerous system path
-                    if PROTECTED_PATHS.iter().any(|p| Path::new(p) == dest_base) {
-                        eprintln!(
-                            "Refusing to sort into protected path: {}",
-                            dest_base.display()
-                        );
+        // pick the preset responsible for this entry
+        let preset = if is_dir {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default();
+            // excluded directories and preset output folders are left alone entirely
+            let excluded_dir = is_excluded_directory(&config.exclusions, &name);
+            if excluded_dir && selected {
+                excluded.push((path.to_path_buf(), "excluded directory".to_string()));
+            }
+            if excluded_dir
+                || output_dirs.contains(path)
+                || (top_level && protected_names.contains(&name.to_lowercase()))
+            {
+                if descends {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+            // only top-level directories are sorted through the "dirs" preset,
+            // nested ones are descended into instead
+            // project folders are neither moved nor sorted from the inside
+            let project = project_marker(path, &markers);
+            // hidden folders the "dirs" preset leaves alone are walked like nested ones
+            match dirs_preset {
+                Some(preset) if top_level && (!hidden || include_hidden(preset)) => {
+                    // the directory is moved as a whole, so its contents are not sorted
+                    if descends {
+                        walker.skip_current_dir();
+                    }
+                    if !selected {
                         continue;
                     }
-
-                    // compute destination file path with basic collision handling
-                    let file_name = match path.file_name() {
-                        Some(n) => n.to_owned(),
-                        None => continue,
-                    };
-                    let desired = dest_base.join(file_name);
-                    let dest_path = reserve_unique_destination(&desired, &mut reserved_dests);
-
-                    // if source and destination are identical, skip
-                    if path == dest_path {
+                    if let Some(marker) = project {
+                        skipped.push((
+                            path.to_path_buf(),
+                            format!(
+                                "project folder (contains {}), use --force to move it",
+                                marker
+                            ),
+                        ));
                         continue;
                     }
+                    // a folder still being downloaded or copied into is left for a later run
+                    if let Some(reason) =
+                        in_progress::busy_dir_reason(path, &config.exclusions, &open_files)
+                    {
+                        excluded.push((path.to_path_buf(), reason));
+                        continue;
+                    }
+                    preset
+                }
+                _ => {
+                    if let Some(marker) = project.filter(|_| descends) {
+                        walker.skip_current_dir();
+                        if selected {
+                            excluded.push((
+                                path.to_path_buf(),
+                                format!("project folder (contains {})", marker),
+                            ));
+                        }
+                    }
+                    continue;
+                }
+            }
+        } else {
+            let metadata = link_meta.or_else(|| entry.metadata().ok());
+            // files still being written are left for a later run
+            if let Some(reason) =
+                in_progress::busy_reason(path, metadata.as_ref(), &config.exclusions, &open_files)
+            {
+                excluded.push((path.to_path_buf(), reason));
+                continue;
+            }
 
-                    planned_moves.push((path.to_path_buf(), dest_path));
+            let size = metadata.map(|m| m.len()).unwrap_or(0);
+            if !config.exclusions.size.allows(size) {
+                excluded.push((
+                    path.to_path_buf(),
+                    "outside min_file_size / max_file_size".to_string(),
+                ));
+                continue;
+            }
+
+            // get the extension of the file (lowercased)
+            let extension = path
+                .extension()
+                .and_then(|s| s.to_str())
+                .map(str::to_lowercase);
+            // with detect_content, the type found in the file header decides over
+            // a missing, unknown or generic extension, and presets can match it by MIME type
+            let detected = if config.general.detect_content {
+                content_candidates(config, &ext_map, path, extension.as_deref())
+            } else {
+                Vec::new()
+            };
+            let candidates: Vec<&PresetConfig> = match extension {
+                _ if !detected.is_empty() => detected
+                    .into_iter()
+                    .chain(catch_all_presets.iter().copied())
+                    .collect(),
+                Some(ext) => ext_map
+                    .get(&ext)
+                    .into_iter()
+                    .flatten()
+                    .chain(catch_all_presets.iter())
+                    .copied()
+                    .collect(),
+                None => extensionless_presets.clone(),
+            };
+            // the first preset whose size limits fit the file takes it
+            let Some(preset) = candidates.iter().find(|p| p.size.allows(size)).copied() else {
+                if candidates.is_empty() {
+                    log::debug!("{}: no preset matches it", path.display());
+                } else {
+                    excluded.push((
+                        path.to_path_buf(),
+                        "outside the file size limits of its presets".to_string(),
+                    ));
                 }
-                Err(e) => eprintln!(
-                    "Could not determine sort destination for '{}': {}",
+                continue;
+            };
+            // nested files are only sorted by presets that recurse deep enough
+            if entry.depth() > preset_max_depth(preset, &config.general) {
+                log::debug!(
+                    "{}: deeper than max_depth of preset {}",
+                    path.display(),
+                    preset.name
+                );
+                continue;
+            }
+            preset
+        };
+
+        if hidden && !include_hidden(preset) {
+            excluded.push((
+                path.to_path_buf(),
+                format!("hidden, {} does not include_hidden", preset.name),
+            ));
+            continue;
+        }
+
+        if let Some(pattern) = preset_exclusions
+            .get(preset.name.as_str())
+            .and_then(|set| set.matching(&target, path))
+        {
+            excluded.push((
+                path.to_path_buf(),
+                format!("excluded by pattern {}", pattern),
+            ));
+            continue;
+        }
+
+        // resolve the destination base path for the preset, expanding its placeholders
+        let date_source = preset.date_source.unwrap_or(config.general.date_source);
+        let dest_base = match dest_base_resolver::get_dest_for_entry(
+            &target,
+            preset,
+            mode.clone(),
+            path,
+            date_source,
+        ) {
+            Ok(dest_base) => dest_base,
+            Err(e) => {
+                log::warn!(
+                    "Could not determine sort destination for {}'{}': {}",
+                    if is_dir { "folder " } else { "" },
                     path.display(),
                     e
-                ),
+                );
+                continue;
+            }
+        };
+
+        // skip if the directory IS the destination folder
+        if path == dest_base {
+            continue;
+        }
+
+        // the first matching rule of the preset picks a subfolder, otherwise the root
+        let dest_base = match preset_rules
+            .get(preset.name.as_str())
+            .and_then(|rules| rules.folder_for(path))
+        {
+            Some(folder) => dest_base.join(folder),
+            None => dest_base,
+        };
+
+        // guard: the destination folder must not be a protected path
+        if let Some(p) = protected.matching(&dest_base) {
+            skipped.push((
+                path.to_path_buf(),
+                format!("destination {} is protected", p.path.display()),
+            ));
+            continue;
+        }
+
+        // compute destination path, renamed according to the naming settings of the preset
+        let file_name = match path.file_name() {
+            Some(n) => n.to_owned(),
+            None => continue,
+        };
+        let file_name = match (file_name.to_str(), preset_naming.get(preset.name.as_str())) {
+            (Some(name), Some(naming)) => naming::apply(name, naming, is_dir).into(),
+            _ => file_name,
+        };
+        let desired = dest_base.join(file_name);
+
+        // if the entry is already where it belongs, skip
+        if path == desired {
+            continue;
+        }
+
+        // handle an existing destination according to the preset (or global) strategy
+        let strategy = preset.on_conflict.unwrap_or(config.general.on_conflict);
+        let pattern = preset
+            .conflict_pattern
+            .as_deref()
+            .or(config.general.conflict_pattern.as_deref())
+            .unwrap_or(DEFAULT_CONFLICT_PATTERN);
+
+        let (dest_path, kind) =
+            match resolve_destination(path, &desired, strategy, pattern, &mut reserved_dests) {
+                Resolution::Move(d) => (d, MoveKind::Move),
+                Resolution::Overwrite(d) => (d, MoveKind::Overwrite),
+                Resolution::Dedupe(d) => (d, MoveKind::Dedupe),
+                Resolution::Skip(reason) => {
+                    skipped.push((path.to_path_buf(), reason));
+                    continue;
+                }
+            };
+
+        // if source and destination are identical, skip
+        if path == dest_path {
+            continue;
+        }
+
+        log::debug!(
+            "{}: preset {} moves it to {}",
+            path.display(),
+            preset.name,
+            dest_path.display()
+        );
+        planned_moves.push(PlannedMove {
+            source: path.to_path_buf(),
+            destination: dest_path,
+            preset: preset.name.clone(),
+            kind,
+        });
+    }
+
+    // -v lists the excluded entries, -vv explains every entry left in place
+    for (path, reason) in excluded.iter().chain(&skipped) {
+        log::debug!("{}: left in place, {}", path.display(), reason);
+    }
+    if options.verbose && text {
+        display_skipped(&target, &excluded);
+    }
+    let skip_reports: Vec<SkipReport> = skipped
+        .iter()
+        .map(|(path, reason)| SkipReport {
+            path: path.clone(),
+            reason: reason.clone(),
+        })
+        .collect();
+
+    // dry-run: show the plan grouped like the real output and stop before touching the disk
+    if options.dry_run {
+        let reports: Vec<MoveReport> = planned_moves
+            .iter()
+            .map(|planned| move_report(planned, entry_size(&planned.source), Ok(None)))
+            .collect();
+        let summary = SortSummary {
+            skipped: skipped.len(),
+            bytes: reports.iter().map(|r| r.bytes).sum(),
+            dry_run: true,
+            ..Default::default()
+        };
+        if text {
+            display_plan(&target, &planned_moves, &skipped);
+        } else {
+            for report in &reports {
+                report::emit_move(options.output, report);
             }
         }
+        report::finish(options.output, &reports, &skip_reports, &summary);
+        return Ok(summary);
     }
 
+    if planned_moves.is_empty() {
+        let summary = SortSummary {
+            skipped: skipped.len(),
+            ..Default::default()
+        };
+        if progress {
+            display_skipped(&target, &skipped);
+        }
+        report::finish(options.output, &[], &skip_reports, &summary);
+        return Ok(summary);
+    }
+
+    // write-ahead journal: every planned move is on disk before anything is touched,
+    // so even a run that crashes half-way through can be undone
+    let journal = match &options.journal_dir {
+        Some(dir) => Some(
+            write_journal_plan(dir, &target, &planned_moves, config.general.symlinks).map_err(
+                |e| format!("failed to write the sort journal, nothing was moved: {}", e),
+            )?,
+        ),
+        None => None,
+    };
+
     // phase 2: pre-create all destination directories (deduped with HashSet)
     let mut unique_dirs: HashSet<PathBuf> = HashSet::new();
-    for (_, dst) in &planned_moves {
-        if let Some(parent) = dst.parent() {
+    for planned in &planned_moves {
+        if let Some(parent) = planned.destination.parent() {
             unique_dirs.insert(parent.to_path_buf());
         }
     }
     // create all the destination directories
     for dir in unique_dirs {
+        // remember which directories did not exist so undo can clean them up
+        let missing: Vec<PathBuf> = dir
+            .ancestors()
+            .take_while(|a| !a.exists())
+            .map(Path::to_path_buf)
+            .collect();
+
         if let Err(e) = fs::create_dir_all(&dir) {
-            eprintln!(
+            log::error!(
                 "Failed to create destination directory '{}': {}",
                 dir.display(),
                 e
             );
+            continue;
+        }
+
+        if let Some(journal) = &journal {
+            for path in missing.into_iter().rev() {
+                journal_record(journal, &JournalRecord::DirCreated { path });
+            }
         }
     }
 
-    // phase 3: execute moves in parallel using rayon and collect results
-    let move_results: Vec<(PathBuf, PathBuf, Result<(), String>)> = planned_moves
-        .par_iter()
-        .map(|(src, dst)| {
-            let result = safe_move(src, dst);
-            (src.to_path_buf(), dst.to_path_buf(), result)
-        })
-        .collect();
+    // phase 3: execute moves in parallel using rayon and collect results.
+    // the pool is sized by --jobs or `threads`, 0 lets rayon use one thread per CPU
+    let threads = options.jobs.or(config.general.threads).unwrap_or(0);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()?;
+    let copy_options = CopyOptions {
+        verify: config.general.verify,
+        throttle: config.general.bandwidth_limit.map(Throttle::new),
+        preserve: config.general.preserve_metadata,
+        symlinks: config.general.symlinks,
+        moved: MovedPaths::new(
+            planned_moves
+                .iter()
+                .map(|p| (p.source.clone(), p.destination.clone())),
+        ),
+    };
+    // links go first: a followed link is copied before what it points to moves away,
+    // and a moved link is pointed at where the run takes its target
+    let (links, entries): (Vec<_>, Vec<_>) = planned_moves
+        .into_iter()
+        .enumerate()
+        .partition(|(_, planned)| is_symlink(&planned.source));
+    let move_results: Vec<(PlannedMove, MoveReport)> = pool.install(|| {
+        let execute = |(id, planned): (usize, PlannedMove)| {
+            // measured before the move, the source is gone afterwards
+            let bytes = entry_size(&planned.source);
+            let result = match &journal {
+                Some(journal) if planned.kind == MoveKind::Overwrite => {
+                    overwrite_with_backup(journal, id, &planned, &copy_options)
+                }
+                _ => execute_move(&planned, &copy_options),
+            };
+            if let Some(journal) = &journal {
+                let record = match &result {
+                    Ok(method) => JournalRecord::Completed {
+                        id,
+                        method: *method,
+                    },
+                    Err(e) => JournalRecord::Failed {
+                        id,
+                        error: e.clone(),
+                    },
+                };
+                journal_record(journal, &record);
+            }
+            let report = move_report(&planned, bytes, result.map(Some));
+            report::emit_move(options.output, &report);
+            (planned, report)
+        };
+        let mut results: Vec<_> = links.into_par_iter().map(execute).collect();
+        results.par_extend(entries.into_par_iter().map(execute));
+        results
+    });
 
     // phase 4: group and display results by destination
-    let mut successful_moves: HashMap<PathBuf, Vec<(PathBuf, PathBuf)>> = HashMap::new();
-    let mut failed_moves: Vec<(PathBuf, PathBuf, String)> = Vec::new();
-    let mut total_moved = 0;
-
-    // group successful moves by destination directory
-    for (src, dst, result) in move_results {
-        match result {
-            Ok(()) => {
-                let dest_dir = dst.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
-                successful_moves
-                    .entry(dest_dir)
-                    .or_insert_with(Vec::new)
-                    .push((src, dst));
-                total_moved += 1;
-            }
-            Err(e) => {
-                failed_moves.push((src, dst, e));
-            }
+    let mut successful_moves: Vec<PlannedMove> = Vec::new();
+    let mut failed_moves: Vec<(PlannedMove, String)> = Vec::new();
+    let mut reports: Vec<MoveReport> = Vec::new();
+
+    for (planned, report) in move_results {
+        match &report.error {
+            None => successful_moves.push(planned),
+            Some(e) => failed_moves.push((planned, e.clone())),
         }
+        reports.push(report);
+    }
+    let total_moved = successful_moves.len();
+    let summary = SortSummary {
+        moved: total_moved,
+        failed: failed_moves.len(),
+        skipped: skipped.len(),
+        bytes: reports
+            .iter()
+            .filter(|r| r.error.is_none())
+            .map(|r| r.bytes)
+            .sum(),
+        dry_run: false,
+        run_id: journal.as_ref().map(|j| j.run_id().to_string()),
+    };
+    if !text {
+        report::finish(options.output, &reports, &skip_reports, &summary);
+        return Ok(summary);
     }
 
-    // display grouped successful moves
-    if !successful_moves.is_empty() {
-        let mut dest_dirs: Vec<_> = successful_moves.keys().collect();
-        dest_dirs.sort();
+    // failures are errors, reported even with -q
+    for (planned, err) in &failed_moves {
+        log::error!(
+            "Failed to move '{}' -> '{}': {}",
+            planned.source.display(),
+            planned.destination.display(),
+            err
+        );
+    }
+    if !progress {
+        return Ok(summary);
+    }
 
-        for dest_dir in dest_dirs {
-            let files = &successful_moves[dest_dir];
-            println!("{}", format!("  → {}", dest_dir.display()).bright_cyan());
+    // display grouped successful moves
+    for (dest_dir, files) in group_by_dest_dir(&successful_moves) {
+        println!("{}", format!("  → {}", dest_dir.display()).bright_cyan());
 
-            for (src, _) in files {
-                if let Some(file_name) = src.file_name() {
-                    println!("{}", format!("    {}", file_name.to_string_lossy()).white());
-                }
+        for planned in files {
+            if let Some(file_name) = planned.source.file_name() {
+                let note = match planned.kind {
+                    MoveKind::Move => "",
+                    MoveKind::Overwrite => " (overwritten)",
+                    MoveKind::Dedupe => " (duplicate removed)",
+                };
+                println!(
+                    "{}",
+                    format!("    {}{}", file_name.to_string_lossy(), note).white()
+                );
             }
-            println!();
         }
+        println!();
     }
 
-    // display failed moves
-    if !failed_moves.is_empty() {
-        for (src, dst, err) in &failed_moves {
-            eprintln!(
-                "{}",
-                format!(
-                    "Failed to move '{}' -> '{}': {}",
-                    src.display(),
-                    dst.display(),
-                    err
-                )
-                .red()
-            );
-        }
-    }
+    display_skipped(&target, &skipped);
 
     // display summary
-    if total_moved > 0 {
+    if total_moved > 0 || !skipped.is_empty() {
         println!(
             "{}",
             format!(
-                "Summary: {} file{} moved",
+                "Summary: {} file{} moved{}",
                 total_moved,
-                if total_moved == 1 { "" } else { "s" }
+                if total_moved == 1 { "" } else { "s" },
+                skipped_note(skipped.len())
             )
             .green()
         );
     }
 
-    Ok(())
+    if let Some(journal) = &journal {
+        println!(
+            "{}",
+            format!(
+                "Run {} recorded. Revert it with: iris undo {}",
+                journal.run_id(),
+                journal.run_id()
+            )
+            .dimmed()
+        );
+    }
+
+    Ok(summary)
+}
+
+/// Report of a planned move: its method once done, or the error that stopped it
+fn move_report(
+    planned: &PlannedMove,
+    bytes: u64,
+    result: Result<Option<MoveMethod>, String>,
+) -> MoveReport {
+    let (method, error) = match result {
+        Ok(method) => (method, None),
+        Err(e) => (None, Some(e)),
+    };
+    MoveReport {
+        source: planned.source.clone(),
+        destination: planned.destination.clone(),
+        preset: planned.preset.clone(),
+        method,
+        bytes,
+        error,
+    }
+}
+
+/// Size of a file, or of every file inside a directory
+fn entry_size(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => WalkDir::new(path)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.metadata().ok())
+            .filter(|m| m.is_file())
+            .map(|m| m.len())
+            .sum(),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Destination folders of the enabled presets, including the ones their placeholders
+/// expand to
+#[derive(Debug, Default)]
+pub(crate) struct OutputDirs(Vec<FolderMatcher>);
+
+impl OutputDirs {
+    /// Check whether `dir` is one of the output folders
+    pub(crate) fn contains(&self, dir: &Path) -> bool {
+        self.0.iter().any(|folders| folders.matches(dir))
+    }
+}
+
+/// Destination folders of the enabled presets for `target`
+pub(crate) fn output_dirs(target: &Path, config: &IrisConfig) -> OutputDirs {
+    OutputDirs(
+        config
+            .presets
+            .iter()
+            .filter(|p| p.enabled)
+            .filter_map(|p| {
+                dest_base_resolver::get_dest_folders(target, p, config.general.mode.clone()).ok()
+            })
+            .collect(),
+    )
 }
 
-/// Reserve a unique destination path.
-/// If `desired` already exists on disk or has been reserved in this run,
-/// generate a hyphenated numeric suffix before the extension (file-1.txt, file-2.txt, ...)
-/// and return the first available path while recording it in `reserved`.
-fn reserve_unique_destination(desired: &Path, reserved: &mut HashSet<PathBuf>) -> PathBuf {
-    if !desired.exists() && !reserved.contains(desired) {
-        reserved.insert(desired.to_path_buf());
-        return desired.to_path_buf();
-    }
-    let parent = desired.parent().unwrap_or_else(|| Path::new("."));
-    let stem = desired
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("file");
-    let ext = desired.extension().and_then(|s| s.to_str());
-    for i in 1.. {
-        let candidate = match ext {
-            Some(e) if !e.is_empty() => parent.join(format!("{}-{}.{}", stem, i, e)),
-            _ => parent.join(format!("{}-{}", stem, i)),
+/// Presets for a file whose type is detected from its content: the ones claiming the
+/// extension of that type and the ones matching its MIME type, in precedence order.
+/// Empty when the file's own extension is claimed by a preset and does not fit the
+/// content (e.g. an .ai file holds a PDF), as that extension is more precise.
+fn content_candidates<'a>(
+    config: &'a IrisConfig,
+    ext_map: &BTreeMap<String, Vec<&'a PresetConfig>>,
+    path: &Path,
+    extension: Option<&str>,
+) -> Vec<&'a PresetConfig> {
+    let Some(detected) = content::detect(path) else {
+        return Vec::new();
+    };
+    if let Some(ext) = extension {
+        if !detected.fits(ext) && !content::is_generic(ext) && ext_map.contains_key(ext) {
+            return Vec::new();
+        }
+    }
+    let claimants = ext_map
+        .get(detected.extension_for(extension))
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    config
+        .presets
+        .iter()
+        .filter(|p| p.enabled)
+        .filter(|p| {
+            claimants.iter().any(|c| c.name == p.name)
+                || p.mime
+                    .iter()
+                    .any(|m| content::mime_matches(m, detected.mime))
+        })
+        .collect()
+}
+
+/// Create the journal for this run and record every planned move before executing any
+fn write_journal_plan(
+    dir: &Path,
+    target: &Path,
+    planned_moves: &[PlannedMove],
+    symlinks: SymlinkPolicy,
+) -> io::Result<JournalWriter> {
+    let journal = JournalWriter::create(dir, target)?;
+    for (id, planned) in planned_moves.iter().enumerate() {
+        // links are recorded as links, that is how undo finds them. a followed link is
+        // replaced by a copy of its target, which is what undo finds at the destination
+        let link_target = match symlinks {
+            SymlinkPolicy::Follow => fs::read_link(&planned.source).ok(),
+            _ => None,
+        };
+        let meta = if link_target.is_some() {
+            fs::metadata(&planned.source).ok()
+        } else {
+            fs::symlink_metadata(&planned.source).ok()
         };
-        if !candidate.exists() && !reserved.contains(&candidate) {
-            reserved.insert(candidate.clone());
-            return candidate;
+        let modified = meta
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+
+        journal.record(&JournalRecord::Planned(MoveEntry {
+            id,
+            source: planned.source.clone(),
+            destination: planned.destination.clone(),
+            preset: planned.preset.clone(),
+            is_dir: meta.as_ref().is_some_and(|m| m.is_dir()),
+            size: meta.as_ref().map_or(0, |m| m.len()),
+            modified,
+            link_target,
+        }))?;
+    }
+    journal.sync()?;
+    Ok(journal)
+}
+
+/// Append a record to the run journal, warning (but not aborting) on failure
+fn journal_record(journal: &JournalWriter, record: &JournalRecord) {
+    if let Err(e) = journal.record(record) {
+        log::warn!("failed to write to the sort journal: {}", e);
+    }
+}
+
+/// Group moves by their destination directory, ordered by directory path
+pub fn group_by_dest_dir(moves: &[PlannedMove]) -> BTreeMap<PathBuf, Vec<&PlannedMove>> {
+    let mut groups: BTreeMap<PathBuf, Vec<&PlannedMove>> = BTreeMap::new();
+    for mv in moves {
+        let dest_dir = mv
+            .destination
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .to_path_buf();
+        groups.entry(dest_dir).or_default().push(mv);
+    }
+    groups
+}
+
+/// Print the planned moves of a dry run, grouped the same way as the real output
+fn display_plan(target: &Path, planned_moves: &[PlannedMove], skipped: &[(PathBuf, String)]) {
+    println!("{}", "Dry run: no files will be moved.".yellow());
+
+    for (dest_dir, moves) in group_by_dest_dir(planned_moves) {
+        println!("{}", format!("  → {}", dest_dir.display()).bright_cyan());
+
+        for planned in moves {
+            // show the source relative to the target and the (possibly suffixed) destination name
+            let src = &planned.source;
+            let dst = &planned.destination;
+            let src_display = src.strip_prefix(target).unwrap_or(src);
+            let dst_name = dst.file_name().unwrap_or(dst.as_os_str());
+            let action = match planned.kind {
+                MoveKind::Move => "would move",
+                MoveKind::Overwrite => "would overwrite with",
+                MoveKind::Dedupe => "would remove duplicate",
+            };
+            println!(
+                "{}",
+                format!(
+                    "    {} {} → {}",
+                    action,
+                    src_display.display(),
+                    dst_name.to_string_lossy()
+                )
+                .white()
+            );
         }
-        if i == usize::MAX {
-            break;
+        println!();
+    }
+
+    display_skipped(target, skipped);
+
+    let total = planned_moves.len();
+    println!(
+        "{}",
+        format!(
+            "Summary: {} file{} would be moved{}",
+            total,
+            if total == 1 { "" } else { "s" },
+            skipped_note(skipped.len())
+        )
+        .green()
+    );
+}
+
+/// Print the entries that were left in place during planning, with the reason why
+fn display_skipped(target: &Path, skipped: &[(PathBuf, String)]) {
+    for (path, reason) in skipped {
+        let path_display = path.strip_prefix(target).unwrap_or(path);
+        println!(
+            "{}",
+            format!("  skipped {} ({})", path_display.display(), reason).dimmed()
+        );
+    }
+    if !skipped.is_empty() {
+        println!();
+    }
+}
+
+/// Suffix for the summary line mentioning skipped entries, if any
+fn skipped_note(count: usize) -> String {
+    if count == 0 {
+        String::new()
+    } else {
+        format!(", {} skipped", count)
+    }
+}
+
+/// Execute a single planned move according to its kind
+fn execute_move(planned: &PlannedMove, copy: &CopyOptions) -> Result<MoveMethod, String> {
+    match planned.kind {
+        MoveKind::Dedupe => fs::remove_file(&planned.source)
+            .map(|_| MoveMethod::Dedupe)
+            .map_err(|e| format!("remove duplicate failed: {}", e)),
+        MoveKind::Move | MoveKind::Overwrite => {
+            safe_move(&planned.source, &planned.destination, copy)
         }
     }
-    // fallback to desired; record it
-    reserved.insert(desired.to_path_buf());
-    desired.to_path_buf()
 }
 
-/// Safely move the source file OR directory to the destination
-fn safe_move(src: &Path, dst: &Path) -> Result<(), String> {
-    if src.is_dir() {
-        // try atomic rename first
-        if fs::rename(src, dst).is_ok() {
-            return Ok(());
+/// Overwrite the destination of a journaled move, keeping the replaced file in a backup
+/// next to it for undo. The backup is recorded before it is made, and put back in place
+/// if the move fails.
+fn overwrite_with_backup(
+    journal: &JournalWriter,
+    id: usize,
+    planned: &PlannedMove,
+    copy: &CopyOptions,
+) -> Result<MoveMethod, String> {
+    let backup = backup_path(&planned.destination, journal.run_id());
+    journal
+        .record(&JournalRecord::Backup {
+            id,
+            path: backup.clone(),
+        })
+        .map_err(|e| format!("failed to record the backup of the destination: {}", e))?;
+    let backed_up = match fs::rename(&planned.destination, &backup) {
+        Ok(()) => true,
+        // the destination is already gone, there is nothing left to overwrite
+        Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+        Err(e) => return Err(format!("backup of the destination failed: {}", e)),
+    };
+
+    let result = execute_move(planned, copy);
+    if result.is_err() && backed_up {
+        if let Err(e) = fs::rename(&backup, &planned.destination) {
+            log::warn!(
+                "{}: could not put the overwritten file back ({}), it is kept at {}",
+                planned.destination.display(),
+                e,
+                backup.display()
+            );
         }
-        // fallback for directories (cross-fs)
-        return copy_delete_dir(src, dst);
+    }
+    result
+}
+
+/// Hidden file next to `destination` keeping what run `run_id` overwrote there
+fn backup_path(destination: &Path, run_id: &str) -> PathBuf {
+    let name = destination
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    destination.with_file_name(format!(".{}.iris-backup-{}", name, run_id))
+}
+
+/// Safely move the source file OR directory to the destination. Copies between filesystems
+/// are made and checked according to `copy`.
+pub(crate) fn safe_move(src: &Path, dst: &Path, copy: &CopyOptions) -> Result<MoveMethod, String> {
+    let is_link = is_symlink(src);
+    if is_link && copy.symlinks != SymlinkPolicy::Follow {
+        return move_link(src, dst, &copy.moved);
     }
 
-    // file path: try fast rename
-    match fs::rename(src, dst) {
-        Ok(_) => return Ok(()),
-        Err(_) => {} // fallthrough to copy fallback
+    // try atomic rename first, a followed link is copied instead so the destination
+    // gets what it points to
+    let rename = if is_link {
+        Err(io::Error::other("following the symbolic link"))
+    } else {
+        fs::rename(src, dst)
+    };
+    let error = match rename {
+        Ok(()) => return Ok(MoveMethod::Rename),
+        Err(e) => e,
+    };
+    log::debug!(
+        "{}: rename failed ({}), copying it instead",
+        src.display(),
+        error
+    );
+
+    if src.is_dir() {
+        // fallback for directories (cross-fs)
+        return copy_delete_dir(src, dst, copy).map(|_| MoveMethod::Copy);
     }
 
     // fallback: copy + verify + delete
-    copy_verify_delete(src, dst)
+    copy_verify_delete(src, dst, copy).map(|_| MoveMethod::Copy)
 }
 
 /// Recursively copy a directory and then delete the source
-fn copy_delete_dir(src: &Path, dst: &Path) -> Result<(), String> {
+fn copy_delete_dir(src: &Path, dst: &Path, copy: &CopyOptions) -> Result<(), String> {
     // create the destination directory
     fs::create_dir_all(dst)
         .map_err(|e| format!("failed to create dir {}: {}", dst.display(), e))?;
+    // directories get their metadata back once filled, copying into them changes their mtime
+    let mut dirs = Vec::new();
+    if copy.preserve {
+        if let Ok(meta) = fs::metadata(src) {
+            dirs.push((src.to_path_buf(), dst.to_path_buf(), meta));
+        }
+    }
 
-    for entry in WalkDir::new(src).min_depth(1) {
-        let entry = entry.map_err(|e| format!("walk error: {}", e))?;
+    // links are recreated as links, unless they are followed to copy what they point to
+    let follow = copy.symlinks == SymlinkPolicy::Follow;
+    for entry in WalkDir::new(src).min_depth(1).follow_links(follow) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            // a dangling link (or one looping back to its folder) cannot be followed
+            Err(e) if follow && e.path().is_some_and(is_symlink) => {
+                let link = e.path().unwrap_or(src);
+                log::warn!("{}: {}, kept as a link", link.display(), e);
+                relink_in_dir(link, src, dst, copy)?;
+                continue;
+            }
+            Err(e) => return Err(format!("walk error: {}", e)),
+        };
         let rel_path = entry
             .path()
             .strip_prefix(src)
             .map_err(|e| format!("strip prefix error: {}", e))?;
         let target_path = dst.join(rel_path);
 
-        if entry.file_type().is_dir() {
+        if entry.path_is_symlink() && !follow {
+            relink_in_dir(entry.path(), src, dst, copy)?;
+        } else if entry.file_type().is_dir() {
             fs::create_dir_all(&target_path)
                 .map_err(|e| format!("failed to create dir {}: {}", target_path.display(), e))?;
+            if copy.preserve {
+                if let Ok(meta) = entry.metadata() {
+                    dirs.push((entry.path().to_path_buf(), target_path, meta));
+                }
+            }
         } else {
-            fs::copy(entry.path(), &target_path)
+            copy_verified(entry.path(), &target_path, copy)
                 .map_err(|e| format!("failed to copy file {}: {}", entry.path().display(), e))?;
         }
     }
 
+    // deepest first, so setting a directory's times does not touch its parent's
+    for (src_dir, dst_dir, meta) in dirs.iter().rev() {
+        warn_not_preserved(src_dir, &preserve_metadata(src_dir, dst_dir, meta));
+    }
+
     // remove source directory after successful copy
     fs::remove_dir_all(src)
         .map_err(|e| format!("failed to remove src dir {}: {}", src.display(), e))?;
     Ok(())
 }
 
-/// Copy the source file to the destination file, verify the size and hash, and delete the source file
-fn copy_verify_delete(src: &Path, dst: &Path) -> Result<(), String> {
-    // perform copy
-    fs::copy(src, dst).map_err(|e| format!("copy failed: {}", e))?;
+/// Recreate the link `link` of the folder `src` in its copy `dst`
+fn relink_in_dir(link: &Path, src: &Path, dst: &Path, copy: &CopyOptions) -> Result<(), String> {
+    let rel_path = link
+        .strip_prefix(src)
+        .map_err(|e| format!("strip prefix error: {}", e))?;
+    copy_link(link, &dst.join(rel_path), (src, dst), &copy.moved)
+        .map_err(|e| format!("failed to copy link {}: {}", link.display(), e))
+}
 
-    // verify size first (quick check)
-    let src_meta = fs::metadata(src).map_err(|e| format!("stat src failed: {}", e))?;
-    let dst_meta = fs::metadata(dst).map_err(|e| format!("stat dst failed: {}", e))?;
-    if src_meta.len() != dst_meta.len() {
-        let _ = fs::remove_file(dst);
-        return Err("size mismatch after copy".into());
-    }
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
 
-    // verify hash (blake3)
-    let src_hash = hash_file(src).map_err(|e| format!("hash src failed: {}", e))?;
-    let dst_hash = hash_file(dst).map_err(|e| format!("hash dst failed: {}", e))?;
-
-    if src_hash != dst_hash {
-        let _ = fs::remove_file(dst);
-        return Err("hash mismatch after copy".into());
-    }
+/// Copy the source file to the destination file, check the copy, and delete the source file
+fn copy_verify_delete(src: &Path, dst: &Path, copy: &CopyOptions) -> Result<(), String> {
+    copy_verified(src, dst, copy)?;
 
     // delete source only after successful verification
     fs::remove_file(src).map_err(|e| format!("remove src failed: {}", e))?;
@@ -441,7 +1246,7 @@ fn copy_verify_delete(src: &Path, dst: &Path) -> Result<(), String> {
 }
 
 /// Hash the file using blake3
-fn hash_file(path: &Path) -> io::Result<blake3::Hash> {
+pub(crate) fn hash_file(path: &Path) -> io::Result<blake3::Hash> {
     use std::io::{BufReader, Read};
     let file = fs::File::open(path)?;
     let mut reader = BufReader::new(file);
@@ -456,3 +1261,795 @@ fn hash_file(path: &Path) -> io::Result<blake3::Hash> {
     }
     Ok(hasher.finalize())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_processor::{
+        ConflictStrategy, DateSource, GeneralConfig, PresetRule, ProtectedPath, ProtectionScope,
+        RenameMethod, SizeLimits,
+    };
+    use chrono::{Datelike, Local};
+    use tempfile::tempdir;
+
+    fn create_test_config() -> IrisConfig {
+        IrisConfig {
+            general: GeneralConfig::default(),
+            presets: vec![PresetConfig {
+                name: "documents".to_string(),
+                enabled: true,
+                extension: vec!["txt".to_string()],
+                relative_path: Some(PathBuf::from("documents")),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sort_moves_matching_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+        fs::write(dir.path().join("photo.png"), "png").unwrap();
+
+        sort(dir.path(), &create_test_config(), &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("documents").join("notes.txt").exists());
+        assert!(!dir.path().join("notes.txt").exists());
+        // files without a matching preset stay where they are
+        assert!(dir.path().join("photo.png").exists());
+    }
+
+    #[test]
+    fn test_dry_run_does_not_touch_disk() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+
+        let options = SortOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        sort(dir.path(), &create_test_config(), &options).unwrap();
+
+        assert!(dir.path().join("notes.txt").exists());
+        assert!(!dir.path().join("documents").exists());
+    }
+
+    #[test]
+    fn test_sort_skip_existing_destination() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("documents")).unwrap();
+        fs::write(dir.path().join("documents").join("notes.txt"), "old").unwrap();
+        fs::write(dir.path().join("notes.txt"), "new").unwrap();
+
+        let mut config = create_test_config();
+        config.presets[0].on_conflict = Some(ConflictStrategy::Skip);
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("notes.txt").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("documents").join("notes.txt")).unwrap(),
+            "old"
+        );
+    }
+
+    #[test]
+    fn test_sort_dedupe_removes_identical_source() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("documents")).unwrap();
+        fs::write(dir.path().join("documents").join("notes.txt"), "same").unwrap();
+        fs::write(dir.path().join("notes.txt"), "same").unwrap();
+
+        let mut config = create_test_config();
+        config.general.on_conflict = ConflictStrategy::Dedupe;
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(!dir.path().join("notes.txt").exists());
+        assert!(!dir.path().join("documents").join("notes-1.txt").exists());
+    }
+
+    #[test]
+    fn test_sort_uses_conflict_pattern() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("documents")).unwrap();
+        fs::write(dir.path().join("documents").join("notes.txt"), "old").unwrap();
+        fs::write(dir.path().join("notes.txt"), "new").unwrap();
+
+        let mut config = create_test_config();
+        config.general.conflict_pattern = Some("{stem} ({n}).{ext}".to_string());
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("documents").join("notes (1).txt").exists());
+    }
+
+    #[test]
+    fn test_recursive_sort_flattens_nested_files() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::write(dir.path().join("a/one.txt"), "1").unwrap();
+        fs::write(dir.path().join("a/b/two.txt"), "2").unwrap();
+
+        // not recursive by default: nested files stay put
+        sort(dir.path(), &create_test_config(), &SortOptions::default()).unwrap();
+        assert!(dir.path().join("a/one.txt").exists());
+
+        let mut config = create_test_config();
+        config.general.recursive = true;
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("documents/one.txt").exists());
+        assert!(dir.path().join("documents/two.txt").exists());
+    }
+
+    #[test]
+    fn test_recursive_sort_respects_max_depth() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        fs::write(dir.path().join("a/one.txt"), "1").unwrap();
+        fs::write(dir.path().join("a/b/two.txt"), "2").unwrap();
+
+        let mut config = create_test_config();
+        config.presets[0].recursive = Some(true);
+        config.presets[0].max_depth = Some(2);
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("documents/one.txt").exists());
+        assert!(dir.path().join("a/b/two.txt").exists());
+    }
+
+    #[test]
+    fn test_recursive_sort_skips_excluded_and_output_dirs() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(dir.path().join("documents/old")).unwrap();
+        fs::write(dir.path().join("node_modules/pkg/readme.txt"), "r").unwrap();
+        fs::write(dir.path().join("documents/old/kept.txt"), "k").unwrap();
+
+        let mut config = create_test_config();
+        config.general.recursive = true;
+        config.exclusions.directories = vec!["node_modules".to_string()];
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("node_modules/pkg/readme.txt").exists());
+        assert!(dir.path().join("documents/old/kept.txt").exists());
+        assert!(!dir.path().join("documents/kept.txt").exists());
+    }
+
+    #[test]
+    fn test_recursive_sort_skips_folders_expanded_from_templates() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("1999/01")).unwrap();
+        fs::create_dir_all(dir.path().join("inbox")).unwrap();
+        fs::write(dir.path().join("1999/01/old.txt"), "o").unwrap();
+        fs::write(dir.path().join("inbox/new.txt"), "n").unwrap();
+
+        let mut config = create_test_config();
+        config.general.recursive = true;
+        config.general.date_source = DateSource::Now;
+        config.presets[0].relative_path = Some(PathBuf::from("{year}/{month}"));
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        // already sorted files are not moved to this month's folder
+        assert!(dir.path().join("1999/01/old.txt").exists());
+        let now = Local::now();
+        let month = dir
+            .path()
+            .join(format!("{}/{:02}", now.year(), now.month()));
+        assert!(month.join("new.txt").exists());
+        assert!(!month.join("old.txt").exists());
+    }
+
+    #[test]
+    fn test_catch_all_and_extensionless_presets() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+        fs::write(dir.path().join("data.xyz"), "xyz").unwrap();
+        fs::write(dir.path().join("Makefile"), "all:").unwrap();
+
+        let mut config = create_test_config();
+        config.presets.push(PresetConfig {
+            name: "misc".to_string(),
+            enabled: true,
+            extension: vec![CATCH_ALL_EXTENSION.to_string()],
+            relative_path: Some(PathBuf::from("misc")),
+            ..Default::default()
+        });
+
+        // extensionless files are not part of the catch-all
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("documents/notes.txt").exists());
+        assert!(dir.path().join("misc/data.xyz").exists());
+        assert!(dir.path().join("Makefile").exists());
+
+        config.presets[1].extensionless = true;
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("misc/Makefile").exists());
+    }
+
+    #[test]
+    fn test_sort_skips_excluded_entries() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+        fs::write(dir.path().join("draft.TXT"), "draft").unwrap();
+        fs::write(dir.path().join("~$report.txt"), "lock").unwrap();
+        fs::write(dir.path().join("private-notes.txt"), "private").unwrap();
+
+        let mut config = create_test_config();
+        config.exclusions.patterns = vec!["draft.*".to_string(), r"regex:^~\$".to_string()];
+        config.presets[0].exclude = vec!["private-*".to_string()];
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("documents/notes.txt").exists());
+        assert!(dir.path().join("draft.TXT").exists());
+        assert!(dir.path().join("~$report.txt").exists());
+        assert!(dir.path().join("private-notes.txt").exists());
+    }
+
+    #[test]
+    fn test_sort_applies_preset_rules() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("invoice_01.txt"), "invoice").unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+
+        let mut config = create_test_config();
+        config.presets[0].rules = vec![PresetRule {
+            filename_contains: Some("invoice".to_string()),
+            folder: PathBuf::from("invoices"),
+            ..Default::default()
+        }];
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir
+            .path()
+            .join("documents/invoices/invoice_01.txt")
+            .exists());
+        // files matching no rule go to the preset root
+        assert!(dir.path().join("documents/notes.txt").exists());
+    }
+
+    #[test]
+    fn test_sort_size_limits() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("small.txt"), vec![b'a'; 10]).unwrap();
+        fs::write(dir.path().join("large.txt"), vec![b'a'; 4096]).unwrap();
+        fs::write(dir.path().join("huge.txt"), vec![b'a'; 8192]).unwrap();
+
+        // large files fall through to the next preset claiming the extension
+        let mut config = create_test_config();
+        config.presets.insert(
+            0,
+            PresetConfig {
+                name: "large".to_string(),
+                enabled: true,
+                extension: vec!["txt".to_string()],
+                relative_path: Some(PathBuf::from("large")),
+                size: SizeLimits {
+                    min: Some(1024),
+                    max: None,
+                },
+                ..Default::default()
+            },
+        );
+        config.exclusions.size.max = Some(5000);
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("documents/small.txt").exists());
+        assert!(dir.path().join("large/large.txt").exists());
+        // above the global maximum: never sorted
+        assert!(dir.path().join("huge.txt").exists());
+    }
+
+    #[test]
+    fn test_sort_expands_destination_placeholders() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+        fs::write(dir.path().join("Readme.TXT"), "readme").unwrap();
+
+        let mut config = create_test_config();
+        config.presets[0].relative_path = Some(PathBuf::from("documents/{ext}/{stem_initial}"));
+        config.presets[0].date_source = Some(DateSource::Now);
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("documents/txt/N/notes.txt").exists());
+        assert!(dir.path().join("documents/txt/R/Readme.TXT").exists());
+    }
+
+    #[test]
+    fn test_sort_renames_destination_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("Meeting Notes.txt"), "notes").unwrap();
+        fs::write(dir.path().join("todo?.txt"), "todo").unwrap();
+
+        let mut config = create_test_config();
+        config.naming.rename_files = true;
+        config.presets[0].naming.rename_method = Some(RenameMethod::Kebab);
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("documents/meeting-notes.txt").exists());
+        // invalid characters on Windows are stripped on every platform
+        assert!(dir.path().join("documents/todo.txt").exists());
+    }
+
+    #[test]
+    fn test_sort_detects_content() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("image.bin"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+        fs::write(dir.path().join("download"), b"%PDF-1.7\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+
+        let mut config = create_test_config();
+        config.general.detect_content = true;
+        config.presets.extend([
+            PresetConfig {
+                name: "images".to_string(),
+                enabled: true,
+                mime: vec!["image/*".to_string()],
+                relative_path: Some(PathBuf::from("images")),
+                ..Default::default()
+            },
+            PresetConfig {
+                name: "system".to_string(),
+                enabled: true,
+                extension: vec!["bin".to_string()],
+                relative_path: Some(PathBuf::from("system")),
+                ..Default::default()
+            },
+            PresetConfig {
+                name: "pdf".to_string(),
+                enabled: true,
+                extension: vec!["pdf".to_string()],
+                relative_path: Some(PathBuf::from("pdf")),
+                ..Default::default()
+            },
+        ]);
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("images/image.bin").exists());
+        assert!(dir.path().join("pdf/download").exists());
+        // undetected files still go by their extension
+        assert!(dir.path().join("documents/notes.txt").exists());
+    }
+
+    #[test]
+    fn test_sort_content_keeps_claimed_extensions() {
+        let dir = tempdir().unwrap();
+        let mut zip = b"PK\x03\x04".to_vec();
+        zip.extend_from_slice(&[0; 26]);
+        zip.extend_from_slice(b"page1.jpg");
+        let files: [(&str, &[u8]); 6] = [
+            (
+                "setup.msi",
+                &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1],
+            ),
+            ("logo.ai", b"%PDF-1.5\n"),
+            ("comic.cbz", &zip),
+            ("places.kmz", &zip),
+            ("photo.avif", b"\0\0\0\x1cftypavif\0\0\0\0"),
+            ("raw.cr3", b"\0\0\0\x18ftypcrx \0\0\0\x01"),
+        ];
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+
+        let mut config = create_test_config();
+        config.general.detect_content = true;
+        let claims = [
+            ("installers", &["msi"][..]),
+            ("design", &["ai"]),
+            ("comics", &["cbz"]),
+            ("maps", &["kmz"]),
+            ("images", &["avif", "cr3"]),
+            ("pdf", &["pdf"]),
+            ("archives", &["zip"]),
+            ("office", &["doc"]),
+            ("video", &["mp4"]),
+        ];
+        for (name, extensions) in claims {
+            config.presets.push(PresetConfig {
+                name: name.to_string(),
+                enabled: true,
+                extension: extensions.iter().map(|e| e.to_string()).collect(),
+                relative_path: Some(PathBuf::from(name)),
+                ..Default::default()
+            });
+        }
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+
+        assert!(dir.path().join("installers/setup.msi").exists());
+        assert!(dir.path().join("design/logo.ai").exists());
+        assert!(dir.path().join("comics/comic.cbz").exists());
+        assert!(dir.path().join("maps/places.kmz").exists());
+        assert!(dir.path().join("images/photo.avif").exists());
+        assert!(dir.path().join("images/raw.cr3").exists());
+    }
+
+    #[test]
+    fn test_sort_skips_files_being_written() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("report.txt.part"), "rep").unwrap();
+        fs::write(dir.path().join("notes.txt.crdownload"), "no").unwrap();
+        fs::write(dir.path().join("fresh.txt"), "fresh").unwrap();
+
+        let mut config = create_test_config();
+        config.presets[0].extension.push("part".to_string());
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        // in-progress downloads are skipped even when a preset claims their extension
+        assert!(dir.path().join("report.txt.part").exists());
+        assert!(dir.path().join("notes.txt.crdownload").exists());
+        assert!(dir.path().join("documents/fresh.txt").exists());
+
+        fs::write(dir.path().join("new.txt"), "new").unwrap();
+        config.exclusions.min_age = std::time::Duration::from_secs(3600);
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("new.txt").exists());
+    }
+
+    #[test]
+    fn test_sort_protects_project_folders() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("iris/.git")).unwrap();
+        fs::create_dir(dir.path().join("photos")).unwrap();
+
+        let mut config = create_test_config();
+        config.presets.push(PresetConfig {
+            name: "dirs".to_string(),
+            enabled: true,
+            relative_path: Some(PathBuf::from("folders")),
+            ..Default::default()
+        });
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("iris/.git").exists());
+        assert!(dir.path().join("folders/photos").exists());
+
+        let options = SortOptions {
+            force: true,
+            ..Default::default()
+        };
+        sort(dir.path(), &config, &options).unwrap();
+        assert!(dir.path().join("folders/iris/.git").exists());
+    }
+
+    #[test]
+    fn test_sort_skips_folders_still_being_written() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("album")).unwrap();
+        fs::write(dir.path().join("album/track.flac"), "track").unwrap();
+
+        let mut config = create_test_config();
+        config.presets.push(PresetConfig {
+            name: "dirs".to_string(),
+            enabled: true,
+            relative_path: Some(PathBuf::from("folders")),
+            ..Default::default()
+        });
+        config.exclusions.min_age = std::time::Duration::from_secs(3600);
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("album/track.flac").exists());
+
+        config.exclusions.min_age = std::time::Duration::ZERO;
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("folders/album/track.flac").exists());
+    }
+
+    #[test]
+    fn test_sort_respects_protected_paths() {
+        let dir = tempdir().unwrap();
+        let root = dunce::canonicalize(dir.path()).unwrap();
+        fs::write(root.join("notes.txt"), "notes").unwrap();
+
+        let mut config = create_test_config();
+        config.general.protected_paths = vec![ProtectedPath {
+            path: root.join("documents"),
+            scope: ProtectionScope::Subtree,
+        }];
+        // the destination is protected, so nothing moves
+        sort(&root, &config, &SortOptions::default()).unwrap();
+        assert!(root.join("notes.txt").exists());
+
+        // the target itself is protected
+        config.general.protected_paths[0].path = root.clone();
+        assert!(sort(&root, &config, &SortOptions::default()).is_err());
+
+        let options = SortOptions {
+            allow_protected: true,
+            ..Default::default()
+        };
+        sort(&root, &config, &options).unwrap();
+        assert!(root.join("documents/notes.txt").exists());
+    }
+
+    #[test]
+    fn test_sort_summary_counts_failed_moves() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+        fs::write(dir.path().join("todo.txt"), "todo").unwrap();
+        // a file where the destination folder should be makes every move fail
+        fs::write(dir.path().join("documents"), "not a folder").unwrap();
+
+        let options = SortOptions {
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+        let summary = sort(dir.path(), &create_test_config(), &options).unwrap();
+        assert_eq!(summary.moved, 0);
+        assert_eq!(summary.failed, 2);
+        assert!(dir.path().join("notes.txt").exists());
+
+        fs::remove_file(dir.path().join("documents")).unwrap();
+        let summary = sort(dir.path(), &create_test_config(), &options).unwrap();
+        assert_eq!(summary.moved, 2);
+        assert_eq!(summary.failed, 0);
+        assert_eq!(summary.bytes, 9);
+    }
+
+    #[test]
+    fn test_sort_refuses_project_target() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]").unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+
+        let config = create_test_config();
+        assert!(sort(dir.path(), &config, &SortOptions::default()).is_err());
+        assert!(dir.path().join("notes.txt").exists());
+
+        // an empty marker list turns the protection off
+        let mut config = create_test_config();
+        config.general.project_markers = Some(Vec::new());
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("documents/notes.txt").exists());
+    }
+
+    #[test]
+    fn test_copied_directory_keeps_its_times() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("album");
+        let dst = dir.path().join("copy");
+        fs::create_dir_all(src.join("day 1")).unwrap();
+        fs::write(src.join("day 1/photo.jpg"), "jpg").unwrap();
+        let before = fs::metadata(src.join("day 1")).unwrap().modified().unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        copy_delete_dir(&src, &dst, &CopyOptions::default()).unwrap();
+        assert!(!src.exists());
+        let copied = fs::metadata(dst.join("day 1")).unwrap().modified().unwrap();
+        assert_eq!(copied, before);
+    }
+
+    #[test]
+    fn test_sort_leaves_hidden_entries_unless_included() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+        fs::write(dir.path().join(".notes.txt"), "hidden").unwrap();
+        fs::write(dir.path().join(".invite.ics"), "ics").unwrap();
+        fs::create_dir_all(dir.path().join(".config/app")).unwrap();
+        fs::write(dir.path().join(".config/app/settings.txt"), "txt").unwrap();
+        fs::write(dir.path().join(".config/event.ics"), "ics").unwrap();
+
+        let mut config = create_test_config();
+        config.general.recursive = true;
+        config.presets.push(PresetConfig {
+            name: "dirs".to_string(),
+            enabled: true,
+            relative_path: Some(PathBuf::from("folders")),
+            ..Default::default()
+        });
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("documents/notes.txt").exists());
+        assert!(dir.path().join(".notes.txt").exists());
+        assert!(dir.path().join(".config/app/settings.txt").exists());
+        assert!(!dir.path().join("folders").exists());
+
+        // a preset opting in sorts hidden files, and the ones in hidden folders
+        config.presets.push(PresetConfig {
+            name: "calendar".to_string(),
+            enabled: true,
+            extension: vec!["ics".to_string()],
+            relative_path: Some(PathBuf::from("calendar")),
+            include_hidden: Some(true),
+            ..Default::default()
+        });
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("calendar/.invite.ics").exists());
+        assert!(dir.path().join("calendar/event.ics").exists());
+        assert!(dir.path().join(".notes.txt").exists());
+        assert!(dir.path().join(".config/app/settings.txt").exists());
+
+        config.general.include_hidden = true;
+        sort(dir.path(), &config, &SortOptions::default()).unwrap();
+        assert!(dir.path().join("documents/.notes.txt").exists());
+        assert!(dir.path().join("folders/.config").exists());
+    }
+
+    /// A target holding links to a file inside it, to a file outside it (relative and
+    /// absolute) and to nothing
+    #[cfg(unix)]
+    fn create_links(root: &Path) -> PathBuf {
+        use std::os::unix::fs::symlink;
+        let target = root.join("target");
+        fs::create_dir_all(target.join("real")).unwrap();
+        fs::create_dir(root.join("outside")).unwrap();
+        fs::write(target.join("real/notes.txt"), "notes").unwrap();
+        fs::write(root.join("outside/out.txt"), "out").unwrap();
+        symlink("real/notes.txt", target.join("inside.txt")).unwrap();
+        symlink("../outside/out.txt", target.join("outside.txt")).unwrap();
+        symlink(root.join("outside/out.txt"), target.join("absolute.txt")).unwrap();
+        symlink("missing.txt", target.join("dangling.txt")).unwrap();
+        target
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sort_moves_links_as_links() {
+        let root = tempdir().unwrap();
+        let target = create_links(root.path());
+
+        let summary = sort(&target, &create_test_config(), &SortOptions::default()).unwrap();
+        assert_eq!(summary.moved, 3);
+        assert_eq!(summary.failed, 0);
+        // dangling links are reported and left alone
+        assert_eq!(summary.skipped, 1);
+        assert!(fs::symlink_metadata(target.join("dangling.txt")).is_ok());
+
+        let docs = target.join("documents");
+        assert_eq!(
+            fs::read_link(docs.join("inside.txt")).unwrap(),
+            PathBuf::from("../real/notes.txt")
+        );
+        assert_eq!(
+            fs::read_link(docs.join("outside.txt")).unwrap(),
+            PathBuf::from("../../outside/out.txt")
+        );
+        assert_eq!(
+            fs::read_link(docs.join("absolute.txt")).unwrap(),
+            root.path().join("outside/out.txt")
+        );
+        assert_eq!(
+            fs::read_to_string(docs.join("inside.txt")).unwrap(),
+            "notes"
+        );
+        assert_eq!(fs::read_to_string(docs.join("outside.txt")).unwrap(), "out");
+        // what the links point to stays where it is
+        assert!(target.join("real/notes.txt").is_file());
+        assert!(root.path().join("outside/out.txt").is_file());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sort_symlink_policies() {
+        let root = tempdir().unwrap();
+        let target = create_links(root.path());
+        let mut config = create_test_config();
+        config.general.symlinks = SymlinkPolicy::Skip;
+        let summary = sort(&target, &config, &SortOptions::default()).unwrap();
+        assert_eq!(summary.moved, 0);
+        assert!(fs::symlink_metadata(target.join("inside.txt")).is_ok());
+
+        // followed links are replaced by a copy of what they point to
+        config.general.symlinks = SymlinkPolicy::Follow;
+        let summary = sort(&target, &config, &SortOptions::default()).unwrap();
+        assert_eq!(summary.moved, 3);
+        assert_eq!(summary.skipped, 1);
+        let docs = target.join("documents");
+        for (name, content) in [("inside.txt", "notes"), ("outside.txt", "out")] {
+            let meta = fs::symlink_metadata(docs.join(name)).unwrap();
+            assert!(meta.is_file());
+            assert_eq!(fs::read_to_string(docs.join(name)).unwrap(), content);
+            assert!(fs::symlink_metadata(target.join(name)).is_err());
+        }
+        assert!(target.join("real/notes.txt").is_file());
+        assert!(root.path().join("outside/out.txt").is_file());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sort_links_to_moved_targets() {
+        for policy in [SymlinkPolicy::MoveLink, SymlinkPolicy::Follow] {
+            let dir = tempdir().unwrap();
+            let target = dir.path();
+            fs::create_dir(target.join("real")).unwrap();
+            fs::write(target.join("real/notes.txt"), "notes").unwrap();
+            std::os::unix::fs::symlink("real/notes.txt", target.join("notes.txt")).unwrap();
+
+            // the folder the link points into is moved by the same run
+            let mut config = create_test_config();
+            config.general.symlinks = policy;
+            config.presets.push(PresetConfig {
+                name: "dirs".to_string(),
+                enabled: true,
+                relative_path: Some(PathBuf::from("folders")),
+                ..Default::default()
+            });
+            let summary = sort(target, &config, &SortOptions::default()).unwrap();
+            assert_eq!(summary.failed, 0);
+            assert_eq!(summary.moved, 2);
+
+            let moved = target.join("documents/notes.txt");
+            assert_eq!(fs::read_to_string(&moved).unwrap(), "notes");
+            assert!(target.join("folders/real/notes.txt").is_file());
+            if policy == SymlinkPolicy::MoveLink {
+                assert_eq!(
+                    fs::read_link(&moved).unwrap(),
+                    PathBuf::from("../folders/real/notes.txt")
+                );
+            } else {
+                assert!(fs::symlink_metadata(&moved).unwrap().is_file());
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copied_directory_keeps_its_links() {
+        use std::os::unix::fs::symlink;
+        let root = tempdir().unwrap();
+        let album = root.path().join("album");
+        fs::create_dir_all(album.join("photos")).unwrap();
+        fs::write(album.join("photos/1.jpg"), "jpg").unwrap();
+        fs::write(root.path().join("shared.jpg"), "shared").unwrap();
+        symlink("photos/1.jpg", album.join("cover.jpg")).unwrap();
+        symlink("../shared.jpg", album.join("shared.jpg")).unwrap();
+        symlink("photos", album.join("latest")).unwrap();
+        symlink("missing.jpg", album.join("dangling.jpg")).unwrap();
+
+        let copy = root.path().join("backup/album");
+        copy_delete_dir(&album, &copy, &CopyOptions::default()).unwrap();
+        assert!(!album.exists());
+        assert_eq!(
+            fs::read_link(copy.join("cover.jpg")).unwrap(),
+            PathBuf::from("photos/1.jpg")
+        );
+        assert_eq!(
+            fs::read_link(copy.join("shared.jpg")).unwrap(),
+            PathBuf::from("../../shared.jpg")
+        );
+        assert_eq!(
+            fs::read_to_string(copy.join("shared.jpg")).unwrap(),
+            "shared"
+        );
+        assert!(fs::symlink_metadata(copy.join("latest"))
+            .unwrap()
+            .is_symlink());
+        assert!(fs::symlink_metadata(copy.join("dangling.jpg"))
+            .unwrap()
+            .is_symlink());
+
+        // followed, the links become copies of what they point to, dangling ones stay links
+        let options = CopyOptions {
+            symlinks: SymlinkPolicy::Follow,
+            ..Default::default()
+        };
+        let followed = root.path().join("followed");
+        copy_delete_dir(&copy, &followed, &options).unwrap();
+        assert!(fs::symlink_metadata(followed.join("shared.jpg"))
+            .unwrap()
+            .is_file());
+        assert!(fs::symlink_metadata(followed.join("latest"))
+            .unwrap()
+            .is_dir());
+        assert!(followed.join("latest/1.jpg").is_file());
+        assert!(fs::symlink_metadata(followed.join("dangling.jpg"))
+            .unwrap()
+            .is_symlink());
+    }
+
+    #[test]
+    fn test_group_by_dest_dir() {
+        let planned = |src: &str, dst: &str| PlannedMove {
+            source: PathBuf::from(src),
+            destination: PathBuf::from(dst),
+            preset: "test".to_string(),
+            kind: MoveKind::Move,
+        };
+        let moves = vec![
+            planned("/t/b.txt", "/t/docs/b.txt"),
+            planned("/t/a.png", "/t/images/a.png"),
+            planned("/t/c.txt", "/t/docs/c.txt"),
+        ];
+        let groups = group_by_dest_dir(&moves);
+        let dirs: Vec<_> = groups.keys().cloned().collect();
+        assert_eq!(
+            dirs,
+            vec![PathBuf::from("/t/docs"), PathBuf::from("/t/images")]
+        );
+        assert_eq!(groups[&PathBuf::from("/t/docs")].len(), 2);
+    }
+}
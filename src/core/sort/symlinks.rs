@@ -0,0 +1,254 @@
+// This module moves symbolic links as links: a relative target is rewritten from the
+// new location of the link, so the link keeps pointing to the same file or folder
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::core::sort::sort::MoveMethod;
+
+/// Where the entries of a run move to, so a link follows its target when the run
+/// moves that too
+#[derive(Debug, Default)]
+pub struct MovedPaths(HashMap<PathBuf, PathBuf>);
+
+impl MovedPaths {
+    pub fn new(moves: impl IntoIterator<Item = (PathBuf, PathBuf)>) -> Self {
+        MovedPaths(moves.into_iter().collect())
+    }
+
+    /// Where `path` ends up, when it or a folder holding it is moved
+    fn new_location(&self, path: &Path) -> Option<PathBuf> {
+        path.ancestors().find_map(|moved| {
+            let destination = self.0.get(moved)?;
+            let rest = path.strip_prefix(moved).ok()?;
+            Some(if rest.as_os_str().is_empty() {
+                destination.clone()
+            } else {
+                destination.join(rest)
+            })
+        })
+    }
+}
+
+/// Whether `path` is a link pointing to nothing, and where it points to if so
+pub fn dangling_target(path: &Path) -> Option<PathBuf> {
+    if fs::metadata(path).is_ok() {
+        return None;
+    }
+    fs::read_link(path).ok()
+}
+
+/// Move the link `src` to `dst`, keeping what it points to wherever `moved` takes it
+pub fn move_link(src: &Path, dst: &Path, moved: &MovedPaths) -> Result<MoveMethod, String> {
+    let target = fs::read_link(src).map_err(|e| format!("read link failed: {}", e))?;
+    let new_target = retarget(&target, src, dst, (src, dst), moved);
+    // absolute targets stay valid wherever the link goes
+    if new_target == target && fs::rename(src, dst).is_ok() {
+        return Ok(MoveMethod::Rename);
+    }
+
+    // made aside then renamed, so an existing destination is replaced in one step
+    let name = dst.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = dst.with_file_name(format!(".{}.iris-link", name));
+    create_link(&new_target, &temporary, is_dir_link(src))
+        .map_err(|e| format!("create link failed: {}", e))?;
+    if let Err(e) = fs::rename(&temporary, dst) {
+        let _ = remove_link(&temporary);
+        return Err(format!("create link failed: {}", e));
+    }
+    remove_link(src).map_err(|e| format!("remove src failed: {}", e))?;
+    Ok(MoveMethod::Relink)
+}
+
+/// Recreate the link `src` at `dst` while the folder `folder.0` is copied to `folder.1`.
+/// Links to what is inside the folder follow it, like links to what `moved` takes
+/// elsewhere, the others keep their target.
+pub fn copy_link(
+    src: &Path,
+    dst: &Path,
+    folder: (&Path, &Path),
+    moved: &MovedPaths,
+) -> io::Result<()> {
+    let target = fs::read_link(src)?;
+    create_link(
+        &retarget(&target, src, dst, folder, moved),
+        dst,
+        is_dir_link(src),
+    )
+}
+
+/// Target for a link moved from `link` to `new_link`, when `folder.0` moves to `folder.1`
+/// and the other entries of the run as `moved` says
+fn retarget(
+    target: &Path,
+    link: &Path,
+    new_link: &Path,
+    folder: (&Path, &Path),
+    moved: &MovedPaths,
+) -> PathBuf {
+    if target.is_absolute() {
+        return target.to_path_buf();
+    }
+    let (Some(dir), Some(new_dir)) = (link.parent(), new_link.parent()) else {
+        return target.to_path_buf();
+    };
+    let resolved = normalize(&dir.join(target));
+    let resolved = match resolved.strip_prefix(folder.0) {
+        Ok(rest) => folder.1.join(rest),
+        Err(_) => moved.new_location(&resolved).unwrap_or(resolved),
+    };
+    relative_path(&resolved, &normalize(new_dir)).unwrap_or(resolved)
+}
+
+/// Resolve `.` and `..` without touching the disk
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// `path` relative to the folder `base`, `None` when they are on different roots
+fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+    // the roots (and drives) must match for a relative path to exist
+    if path.has_root() != base.has_root()
+        || (path.has_root() && path_components.peek() != base_components.peek())
+    {
+        return None;
+    }
+    while let (Some(p), Some(b)) = (path_components.peek(), base_components.peek()) {
+        if p != b {
+            break;
+        }
+        path_components.next();
+        base_components.next();
+    }
+
+    let mut relative: PathBuf = base_components.map(|_| Component::ParentDir).collect();
+    relative.extend(path_components);
+    if relative.as_os_str().is_empty() {
+        relative.push(Component::CurDir);
+    }
+    Some(relative)
+}
+
+/// Whether the link points to a folder, read before its target may move
+fn is_dir_link(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_dir())
+}
+
+/// Create the link `link` to `target`. `is_dir` tells windows whether it is a folder link.
+#[cfg(unix)]
+pub fn create_link(target: &Path, link: &Path, _is_dir: bool) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+pub fn create_link(target: &Path, link: &Path, is_dir: bool) -> io::Result<()> {
+    if is_dir {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn create_link(_target: &Path, _link: &Path, _is_dir: bool) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Remove a link, which is a folder on windows when it points to one
+pub fn remove_link(path: &Path) -> io::Result<()> {
+    fs::remove_file(path).or_else(|e| fs::remove_dir(path).map_err(|_| e))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_retarget() {
+        let none = MovedPaths::default();
+        let moved = (Path::new("/t/link"), Path::new("/t/docs/link"));
+        let retargeted = |target: &str| retarget(Path::new(target), moved.0, moved.1, moved, &none);
+        assert_eq!(retargeted("notes.txt"), PathBuf::from("../notes.txt"));
+        assert_eq!(retargeted("../out/a.txt"), PathBuf::from("../../out/a.txt"));
+        assert_eq!(retargeted("./docs/a.txt"), PathBuf::from("a.txt"));
+        assert_eq!(retargeted("/abs/a.txt"), PathBuf::from("/abs/a.txt"));
+
+        // inside a moved folder, links to its own content are left as they are
+        let folder = (Path::new("/t/album"), Path::new("/mnt/album"));
+        let link = Path::new("/t/album/cover.jpg");
+        let new_link = Path::new("/mnt/album/cover.jpg");
+        assert_eq!(
+            retarget(Path::new("photos/1.jpg"), link, new_link, folder, &none),
+            PathBuf::from("photos/1.jpg")
+        );
+        assert_eq!(
+            retarget(Path::new("../shared.jpg"), link, new_link, folder, &none),
+            PathBuf::from("../../t/shared.jpg")
+        );
+
+        // a target moved by the same run, or inside a folder it moves, is followed
+        let run = MovedPaths::new([
+            (PathBuf::from("/t/real"), PathBuf::from("/t/folders/real")),
+            (PathBuf::from("/t/a.txt"), PathBuf::from("/t/docs/a.txt")),
+        ]);
+        let retargeted = |target: &str| retarget(Path::new(target), moved.0, moved.1, moved, &run);
+        assert_eq!(
+            retargeted("real/notes.txt"),
+            PathBuf::from("../folders/real/notes.txt")
+        );
+        assert_eq!(retargeted("a.txt"), PathBuf::from("a.txt"));
+        assert_eq!(retargeted("reality.txt"), PathBuf::from("../reality.txt"));
+    }
+
+    #[test]
+    fn test_move_link_keeps_its_target() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.txt"), "notes").unwrap();
+        fs::create_dir(dir.path().join("docs")).unwrap();
+        let link = dir.path().join("link.txt");
+        std::os::unix::fs::symlink("notes.txt", &link).unwrap();
+
+        let moved = dir.path().join("docs/link.txt");
+        assert_eq!(
+            move_link(&link, &moved, &MovedPaths::default()),
+            Ok(MoveMethod::Relink)
+        );
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert_eq!(
+            fs::read_link(&moved).unwrap(),
+            PathBuf::from("../notes.txt")
+        );
+        assert_eq!(fs::read_to_string(&moved).unwrap(), "notes");
+    }
+
+    #[test]
+    fn test_dangling_target() {
+        let dir = tempdir().unwrap();
+        let link = dir.path().join("link.txt");
+        std::os::unix::fs::symlink("gone.txt", &link).unwrap();
+        assert_eq!(dangling_target(&link), Some(PathBuf::from("gone.txt")));
+
+        fs::write(dir.path().join("gone.txt"), "back").unwrap();
+        assert_eq!(dangling_target(&link), None);
+        assert_eq!(dangling_target(&dir.path().join("gone.txt")), None);
+    }
+}
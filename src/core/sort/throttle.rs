@@ -0,0 +1,92 @@
+// This module caps the bandwidth of the copies made when a move crosses filesystems,
+// so a large sort onto a USB drive or a NAS does not saturate the disk
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How much transfer time the bucket holds: after an idle period, this is all that can
+/// go through at full speed before the limit applies again
+const BURST: Duration = Duration::from_millis(250);
+
+/// A bandwidth limit shared by every copy of a run, whatever thread makes it.
+/// It is a token bucket: bytes are taken from it and it refills at the limit.
+#[derive(Debug)]
+pub struct Throttle {
+    bytes_per_sec: u64,
+    // most bytes the bucket holds
+    capacity: f64,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    // bytes available, negative while the callers owe time
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Throttle {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1);
+        let capacity = bytes_per_sec as f64 * BURST.as_secs_f64();
+        Throttle {
+            bytes_per_sec,
+            capacity,
+            state: Mutex::new(Bucket {
+                tokens: capacity,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Account for `bytes` just transferred, sleeping until the bucket is no longer in debt
+    pub fn consume(&self, bytes: u64) {
+        let wait = {
+            let Ok(mut bucket) = self.state.lock() else {
+                return;
+            };
+            let now = Instant::now();
+            let refill =
+                now.duration_since(bucket.refilled).as_secs_f64() * self.bytes_per_sec as f64;
+            bucket.tokens = (bucket.tokens + refill).min(self.capacity) - bytes as f64;
+            bucket.refilled = now;
+            if bucket.tokens < 0.0 {
+                Duration::from_secs_f64(-bucket.tokens / self.bytes_per_sec as f64)
+            } else {
+                Duration::ZERO
+            }
+        };
+        // sleep without the lock, so the other copies keep accounting
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consume_waits_for_the_rate() {
+        // 100 KB at 1 MB/s, once the 250 KB burst is used, is due after 100ms
+        let throttle = Throttle::new(1_000_000);
+        throttle.consume(250_000);
+        let start = Instant::now();
+        throttle.consume(50_000);
+        throttle.consume(50_000);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[test]
+    fn test_idle_time_only_buys_a_bounded_burst() {
+        let throttle = Throttle::new(1_000_000);
+        // idle for longer than the burst
+        std::thread::sleep(Duration::from_millis(400));
+        let start = Instant::now();
+        throttle.consume(250_000);
+        assert!(start.elapsed() < Duration::from_millis(50));
+        // the idle time beyond the burst was not saved up
+        throttle.consume(100_000);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+}
@@ -0,0 +1,76 @@
+// This module decides how deep the sort walks into the target and which presets apply at each depth
+use crate::config::config_processor::{GeneralConfig, IrisConfig, PresetConfig};
+
+/// Deepest level a preset sorts from, 1 being the files directly inside the target
+pub fn preset_max_depth(preset: &PresetConfig, general: &GeneralConfig) -> usize {
+    let recursive = preset.recursive.unwrap_or(general.recursive);
+    if !recursive {
+        return 1;
+    }
+    preset
+        .max_depth
+        .or(general.max_depth)
+        .unwrap_or(usize::MAX)
+        .max(1)
+}
+
+/// Deepest level the walker has to reach to serve every enabled preset
+pub fn walk_depth(config: &IrisConfig) -> usize {
+    config
+        .presets
+        .iter()
+        .filter(|p| p.enabled)
+        .map(|p| preset_max_depth(p, &config.general))
+        .max()
+        .unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(recursive: Option<bool>, max_depth: Option<usize>) -> PresetConfig {
+        PresetConfig {
+            name: "test".to_string(),
+            enabled: true,
+            recursive,
+            max_depth,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_preset_max_depth() {
+        let general = GeneralConfig::default();
+        assert_eq!(preset_max_depth(&preset(None, None), &general), 1);
+        // max_depth only applies to recursive presets
+        assert_eq!(preset_max_depth(&preset(None, Some(3)), &general), 1);
+        assert_eq!(preset_max_depth(&preset(Some(true), Some(3)), &general), 3);
+        assert_eq!(
+            preset_max_depth(&preset(Some(true), None), &general),
+            usize::MAX
+        );
+
+        let recursive_general = GeneralConfig {
+            recursive: true,
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(preset_max_depth(&preset(None, None), &recursive_general), 2);
+        assert_eq!(
+            preset_max_depth(&preset(Some(false), None), &recursive_general),
+            1
+        );
+    }
+
+    #[test]
+    fn test_walk_depth_uses_deepest_enabled_preset() {
+        let mut disabled = preset(Some(true), Some(10));
+        disabled.enabled = false;
+        let config = IrisConfig {
+            presets: vec![preset(None, None), preset(Some(true), Some(4)), disabled],
+            ..Default::default()
+        };
+        assert_eq!(walk_depth(&config), 4);
+    }
+}
@@ -0,0 +1,616 @@
+// This module reverts a sort run by replaying its journal in reverse.
+// Entries whose destination changed after the sort are reported instead of being restored.
+use colored::Colorize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use crate::core::journal::{self, JournalMove, JournalRecord, MoveEntry, MoveOutcome, RunRecord};
+use crate::core::sort::copy::CopyOptions;
+use crate::core::sort::sort::{safe_move, MoveMethod};
+use crate::core::sort::symlinks::{create_link, remove_link, MovedPaths};
+use crate::logging;
+
+/// Result of undoing a single run
+#[derive(Debug, Default)]
+pub struct UndoReport {
+    // (current location, restored location)
+    pub restored: Vec<(PathBuf, PathBuf)>,
+    // (current location, reason it could not be restored)
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+/// Undo the given run, or the most recent one that has not been undone yet.
+/// Fails when nothing could be restored.
+pub fn undo(
+    journal_dir: &Path,
+    run_id: Option<&str>,
+) -> Result<UndoReport, Box<dyn std::error::Error>> {
+    let run = match run_id {
+        Some(id) => {
+            let path = journal::run_file_path(journal_dir, id)?;
+            if !path.exists() {
+                return Err(format!("no sort run with id '{}' was found", id).into());
+            }
+            journal::load_run(&path)?
+        }
+        None => journal::latest_undoable_run(journal_dir)?
+            .ok_or("there are no sort runs left to undo")?,
+    };
+
+    if let Some(when) = run.undone {
+        return Err(format!(
+            "run '{}' was already undone on {}",
+            run.header.run_id,
+            when.format("%Y-%m-%d %H:%M:%S")
+        )
+        .into());
+    }
+
+    let journal_path = journal::run_file_path(journal_dir, &run.header.run_id)?;
+    let report = undo_run(&run, &journal_path);
+
+    // mark the run as undone so it is not picked up again. entries left in place can be
+    // retried by the run id, while `iris undo` moves on to older runs
+    let record = if report.skipped.is_empty() {
+        JournalRecord::Undone {
+            timestamp: Local::now(),
+        }
+    } else {
+        JournalRecord::PartiallyUndone {
+            timestamp: Local::now(),
+        }
+    };
+    journal::append_record(&journal_path, &record)?;
+
+    display_report(&run, &report);
+    if report.restored.is_empty() {
+        return Err(format!("nothing was restored from run '{}'", run.header.run_id).into());
+    }
+    Ok(report)
+}
+
+/// Restore every move of the run (newest first) and remove directories the run created.
+/// Each restored move is recorded in the journal at `journal_path`.
+fn undo_run(run: &RunRecord, journal_path: &Path) -> UndoReport {
+    let mut report = UndoReport::default();
+    // links moved back point to where their target is put back
+    let copy = CopyOptions {
+        moved: MovedPaths::new(
+            run.moves
+                .iter()
+                .filter(|m| !matches!(m.outcome, MoveOutcome::Failed(_)))
+                .map(|m| (m.entry.destination.clone(), m.entry.source.clone())),
+        ),
+        ..Default::default()
+    };
+
+    for journal_move in run.moves.iter().rev() {
+        let entry = &journal_move.entry;
+        match check_restorable(journal_move) {
+            Ok(true) => {}
+            // nothing happened to this entry, so there is nothing to restore. a file it
+            // overwrote may still be in its backup, e.g. when the run was interrupted
+            Ok(false) => {
+                restore_backup(journal_move, &mut report);
+                continue;
+            }
+            Err(reason) => {
+                report.skipped.push((entry.destination.clone(), reason));
+                continue;
+            }
+        }
+
+        if let Some(parent) = entry.source.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                report.skipped.push((
+                    entry.destination.clone(),
+                    format!("failed to recreate '{}': {}", parent.display(), e),
+                ));
+                continue;
+            }
+        }
+
+        // a deduplicated source was removed, so restore it from its identical copy
+        let restore = if let Some(link_target) = &entry.link_target {
+            restore_followed_link(entry, link_target)
+        } else if journal_move.outcome == MoveOutcome::Completed(MoveMethod::Dedupe) {
+            fs::copy(&entry.destination, &entry.source)
+                .map(|_| MoveMethod::Copy)
+                .map_err(|e| format!("copy failed: {}", e))
+        } else {
+            safe_move(&entry.destination, &entry.source, &copy)
+        };
+
+        match restore {
+            Ok(_) => {
+                let record = JournalRecord::Restored { id: entry.id };
+                if let Err(e) = journal::append_record(journal_path, &record) {
+                    log::warn!("failed to write to the sort journal: {}", e);
+                }
+                report
+                    .restored
+                    .push((entry.destination.clone(), entry.source.clone()));
+                restore_backup(journal_move, &mut report);
+            }
+            Err(e) => report.skipped.push((entry.destination.clone(), e)),
+        }
+    }
+
+    // remove the directories created by the sort, deepest first, if they are empty now
+    let mut created_dirs = run.created_dirs.clone();
+    created_dirs.sort_by_key(|d| std::cmp::Reverse(d.components().count()));
+    for dir in created_dirs {
+        let _ = fs::remove_dir(dir);
+    }
+
+    report
+}
+
+/// Put back a link the sort followed, in place of the copy of what it pointed to
+fn restore_followed_link(entry: &MoveEntry, link_target: &Path) -> Result<MoveMethod, String> {
+    create_link(link_target, &entry.source, entry.is_dir)
+        .map_err(|e| format!("create link failed: {}", e))?;
+    let removed = if entry.is_dir {
+        fs::remove_dir_all(&entry.destination)
+    } else {
+        fs::remove_file(&entry.destination)
+    };
+    if let Err(e) = removed {
+        let _ = remove_link(&entry.source);
+        return Err(format!("remove copy failed: {}", e));
+    }
+    Ok(MoveMethod::Relink)
+}
+
+/// Put back the file an overwrite replaced, once its destination is free again
+fn restore_backup(journal_move: &JournalMove, report: &mut UndoReport) {
+    let Some(backup) = &journal_move.backup else {
+        return;
+    };
+    // no backup was made, or it was already put back
+    if fs::symlink_metadata(backup).is_err() {
+        return;
+    }
+    let destination = &journal_move.entry.destination;
+    if fs::symlink_metadata(destination).is_ok() {
+        report.skipped.push((
+            backup.clone(),
+            format!("'{}' is occupied", destination.display()),
+        ));
+        return;
+    }
+    match fs::rename(backup, destination) {
+        Ok(()) => report.restored.push((backup.clone(), destination.clone())),
+        Err(e) => report.skipped.push((backup.clone(), e.to_string())),
+    }
+}
+
+/// Decide whether a journal entry can be restored.
+/// Returns `Ok(false)` when the move never happened or was already restored, and `Err`
+/// with a reason when the destination changed since the sort or the original location
+/// is occupied.
+fn check_restorable(journal_move: &JournalMove) -> Result<bool, String> {
+    let entry = &journal_move.entry;
+    if journal_move.restored {
+        return Ok(false);
+    }
+    let method = match &journal_move.outcome {
+        MoveOutcome::Completed(method) => Some(*method),
+        MoveOutcome::Failed(_) => return Ok(false),
+        // the run was interrupted: the move may or may not have happened
+        MoveOutcome::Pending => {
+            if entry.source.exists() && !entry.destination.exists() {
+                return Ok(false);
+            }
+            None
+        }
+    };
+
+    let meta = fs::symlink_metadata(&entry.destination)
+        .map_err(|_| "destination no longer exists".to_string())?;
+
+    if destination_changed(entry, &meta, method) {
+        return Err("destination changed since the sort".to_string());
+    }
+
+    if entry.source.exists() {
+        return Err(format!(
+            "original location '{}' is occupied",
+            entry.source.display()
+        ));
+    }
+
+    Ok(true)
+}
+
+/// Compare the current destination against what was recorded when the move was planned
+fn destination_changed(entry: &MoveEntry, meta: &fs::Metadata, method: Option<MoveMethod>) -> bool {
+    if meta.is_dir() != entry.is_dir {
+        return true;
+    }
+    // a recreated link has a new target, its size and times are not comparable
+    if method == Some(MoveMethod::Relink) {
+        return !meta.file_type().is_symlink();
+    }
+    // directory contents are not tracked, only their presence
+    if entry.is_dir {
+        return false;
+    }
+    if meta.len() != entry.size {
+        return true;
+    }
+    // a rename keeps the modification time, the copy fallback does not
+    if method == Some(MoveMethod::Rename) {
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        if modified != entry.modified {
+            return true;
+        }
+    }
+    false
+}
+
+/// Print the restored and skipped entries of an undo. With -q only the entries that
+/// could not be restored are reported.
+fn display_report(run: &RunRecord, report: &UndoReport) {
+    for (path, reason) in &report.skipped {
+        log::error!("Could not restore '{}': {}", path.display(), reason);
+    }
+    if !report.skipped.is_empty() {
+        log::warn!(
+            "run `iris undo {}` again to retry the files that could not be restored",
+            run.header.run_id
+        );
+    }
+    if logging::quiet() {
+        return;
+    }
+
+    println!(
+        "Undoing run {} ({}) in: {}",
+        run.header.run_id,
+        run.header.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        run.header.target.display()
+    );
+
+    for (from, to) in &report.restored {
+        println!(
+            "{}",
+            format!("    {} → {}", from.display(), to.display()).white()
+        );
+    }
+
+    let total = report.restored.len();
+    println!(
+        "{}",
+        format!(
+            "Summary: {} file{} restored",
+            total,
+            if total == 1 { "" } else { "s" }
+        )
+        .green()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_processor::{
+        ConflictStrategy, GeneralConfig, IrisConfig, PresetConfig, SymlinkPolicy,
+    };
+    use crate::core::sort::sort::{sort, SortOptions};
+    use tempfile::tempdir;
+
+    fn create_test_config() -> IrisConfig {
+        IrisConfig {
+            general: GeneralConfig::default(),
+            presets: vec![PresetConfig {
+                name: "documents".to_string(),
+                enabled: true,
+                extension: vec!["txt".to_string()],
+                relative_path: Some(PathBuf::from("documents")),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn sort_with_journal(target: &Path, journal_dir: &Path) {
+        let options = SortOptions {
+            journal_dir: Some(journal_dir.to_path_buf()),
+            ..Default::default()
+        };
+        sort(target, &create_test_config(), &options).unwrap();
+    }
+
+    #[test]
+    fn test_undo_restores_last_run() {
+        let target = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        fs::write(target.path().join("notes.txt"), "notes").unwrap();
+
+        sort_with_journal(target.path(), journal_dir.path());
+        assert!(target.path().join("documents/notes.txt").exists());
+
+        let report = undo(journal_dir.path(), None).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert!(target.path().join("notes.txt").exists());
+        // the destination folder created by the sort is cleaned up
+        assert!(!target.path().join("documents").exists());
+
+        // the run is marked as undone
+        assert!(undo(journal_dir.path(), None).is_err());
+    }
+
+    #[test]
+    fn test_undo_restores_renamed_collision() {
+        let target = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        fs::create_dir(target.path().join("documents")).unwrap();
+        fs::write(target.path().join("documents/notes.txt"), "old").unwrap();
+        fs::write(target.path().join("notes.txt"), "new").unwrap();
+
+        sort_with_journal(target.path(), journal_dir.path());
+        assert!(target.path().join("documents/notes-1.txt").exists());
+
+        undo(journal_dir.path(), None).unwrap();
+        assert_eq!(
+            fs::read_to_string(target.path().join("notes.txt")).unwrap(),
+            "new"
+        );
+        assert!(!target.path().join("documents/notes-1.txt").exists());
+        // pre-existing directories are left in place
+        assert!(target.path().join("documents/notes.txt").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_undo_restores_moved_link() {
+        let target = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        fs::create_dir(target.path().join("real")).unwrap();
+        fs::write(target.path().join("real/notes.txt"), "notes").unwrap();
+        let link = target.path().join("notes.txt");
+        std::os::unix::fs::symlink("real/notes.txt", &link).unwrap();
+
+        sort_with_journal(target.path(), journal_dir.path());
+        assert!(fs::symlink_metadata(&link).is_err());
+
+        let report = undo(journal_dir.path(), None).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert_eq!(
+            fs::read_link(&link).unwrap(),
+            PathBuf::from("real/notes.txt")
+        );
+        assert!(!target.path().join("documents").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_undo_puts_links_back() {
+        for policy in [SymlinkPolicy::MoveLink, SymlinkPolicy::Follow] {
+            let target = tempdir().unwrap();
+            let journal_dir = tempdir().unwrap();
+            fs::create_dir(target.path().join("real")).unwrap();
+            fs::write(target.path().join("real/notes.txt"), "notes").unwrap();
+            let link = target.path().join("notes.txt");
+            std::os::unix::fs::symlink("real/notes.txt", &link).unwrap();
+
+            // the folder the link points into moves in the same run
+            let mut config = create_test_config();
+            config.general.symlinks = policy;
+            config.presets.push(PresetConfig {
+                name: "dirs".to_string(),
+                enabled: true,
+                relative_path: Some(PathBuf::from("folders")),
+                ..Default::default()
+            });
+            let options = SortOptions {
+                journal_dir: Some(journal_dir.path().to_path_buf()),
+                ..Default::default()
+            };
+            sort(target.path(), &config, &options).unwrap();
+
+            let report = undo(journal_dir.path(), None).unwrap();
+            assert!(report.skipped.is_empty(), "{:?}", report.skipped);
+            assert_eq!(report.restored.len(), 2);
+            // a followed link comes back as a link, not as the copy made of its target
+            assert_eq!(
+                fs::read_link(&link).unwrap(),
+                PathBuf::from("real/notes.txt")
+            );
+            assert_eq!(fs::read_to_string(&link).unwrap(), "notes");
+            assert!(!target.path().join("documents").exists());
+            assert!(!target.path().join("folders").exists());
+        }
+    }
+
+    #[test]
+    fn test_undo_reports_changed_destination() {
+        let target = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        fs::write(target.path().join("notes.txt"), "notes").unwrap();
+
+        sort_with_journal(target.path(), journal_dir.path());
+        fs::write(target.path().join("documents/notes.txt"), "edited since").unwrap();
+
+        // nothing could be restored, which is an error
+        assert!(undo(journal_dir.path(), None).is_err());
+        assert!(target.path().join("documents/notes.txt").exists());
+        // the run is not picked again by `iris undo` without a run id
+        let err = undo(journal_dir.path(), None).unwrap_err();
+        assert!(err.to_string().contains("no sort runs left"));
+    }
+
+    #[test]
+    fn test_undo_skips_runs_where_every_move_failed() {
+        let target = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        fs::write(target.path().join("notes.txt"), "notes").unwrap();
+        sort_with_journal(target.path(), journal_dir.path());
+
+        // a later run that planned a move but never completed it
+        let journal = journal::JournalWriter::create(journal_dir.path(), target.path()).unwrap();
+        journal
+            .record(&JournalRecord::Planned(MoveEntry {
+                id: 0,
+                source: target.path().join("todo.txt"),
+                destination: target.path().join("documents/todo.txt"),
+                preset: "documents".to_string(),
+                is_dir: false,
+                size: 4,
+                modified: None,
+                link_target: None,
+            }))
+            .unwrap();
+        journal
+            .record(&JournalRecord::Failed {
+                id: 0,
+                error: "permission denied".to_string(),
+            })
+            .unwrap();
+
+        let report = undo(journal_dir.path(), None).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert!(target.path().join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_undo_interrupted_run() {
+        let target = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        let source = target.path().join("notes.txt");
+        let destination = target.path().join("documents/notes.txt");
+        fs::write(&source, "notes").unwrap();
+
+        // simulate a crash: the move was planned and executed but never confirmed
+        let journal = journal::JournalWriter::create(journal_dir.path(), target.path()).unwrap();
+        journal
+            .record(&JournalRecord::Planned(MoveEntry {
+                id: 0,
+                source: source.clone(),
+                destination: destination.clone(),
+                preset: "documents".to_string(),
+                is_dir: false,
+                size: 5,
+                modified: None,
+                link_target: None,
+            }))
+            .unwrap();
+        fs::create_dir(target.path().join("documents")).unwrap();
+        fs::rename(&source, &destination).unwrap();
+
+        let report = undo(journal_dir.path(), Some(journal.run_id())).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert!(source.exists());
+        assert!(!destination.exists());
+    }
+
+    #[test]
+    fn test_partial_undo_can_be_retried() {
+        let target = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        fs::write(target.path().join("notes.txt"), "notes").unwrap();
+        fs::write(target.path().join("todo.txt"), "todo").unwrap();
+        sort_with_journal(target.path(), journal_dir.path());
+
+        // the original location of one file is taken, the other one is restored
+        fs::write(target.path().join("todo.txt"), "new todo").unwrap();
+        let report = undo(journal_dir.path(), None).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert!(target.path().join("notes.txt").exists());
+
+        // the run can be retried by its id, and only the skipped file is tried again
+        let run_id = journal::list_runs(journal_dir.path()).unwrap()[0]
+            .header
+            .run_id
+            .clone();
+        assert!(undo(journal_dir.path(), None).is_err());
+        fs::remove_file(target.path().join("todo.txt")).unwrap();
+        let report = undo(journal_dir.path(), Some(&run_id)).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert!(report.skipped.is_empty());
+        assert_eq!(
+            fs::read_to_string(target.path().join("todo.txt")).unwrap(),
+            "todo"
+        );
+        assert!(undo(journal_dir.path(), Some(&run_id)).is_err());
+    }
+
+    #[test]
+    fn test_undo_restores_overwritten_file() {
+        let target = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        fs::create_dir(target.path().join("documents")).unwrap();
+        fs::write(target.path().join("documents/notes.txt"), "old notes").unwrap();
+        fs::write(target.path().join("notes.txt"), "new notes").unwrap();
+
+        let mut config = create_test_config();
+        config.general.on_conflict = ConflictStrategy::Overwrite;
+        let options = SortOptions {
+            journal_dir: Some(journal_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        sort(target.path(), &config, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(target.path().join("documents/notes.txt")).unwrap(),
+            "new notes"
+        );
+
+        let report = undo(journal_dir.path(), None).unwrap();
+        assert!(report.skipped.is_empty());
+        assert_eq!(
+            fs::read_to_string(target.path().join("notes.txt")).unwrap(),
+            "new notes"
+        );
+        assert_eq!(
+            fs::read_to_string(target.path().join("documents/notes.txt")).unwrap(),
+            "old notes"
+        );
+        // the backup is gone once it is put back
+        assert_eq!(
+            fs::read_dir(target.path().join("documents"))
+                .unwrap()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_undo_unknown_run_id() {
+        let journal_dir = tempdir().unwrap();
+        assert!(undo(journal_dir.path(), Some("19700101-000000")).is_err());
+    }
+
+    #[test]
+    fn test_undo_rejects_run_ids_outside_the_journal() {
+        let journal_dir = tempdir().unwrap();
+        for id in ["../run", "runs/run", "..\\run", ".."] {
+            let err = undo(journal_dir.path(), Some(id)).unwrap_err();
+            assert!(err.to_string().contains("invalid run id"));
+        }
+    }
+
+    #[test]
+    fn test_undo_skips_corrupt_journals() {
+        let target = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        fs::write(target.path().join("notes.txt"), "notes").unwrap();
+        sort_with_journal(target.path(), journal_dir.path());
+        fs::write(
+            journal_dir.path().join("broken.jsonl"),
+            "not a journal\n{}\n",
+        )
+        .unwrap();
+
+        let report = undo(journal_dir.path(), None).unwrap();
+        assert_eq!(report.restored.len(), 1);
+        assert!(target.path().join("notes.txt").exists());
+    }
+}
@@ -0,0 +1,394 @@
+// This module keeps a folder sorted: `iris watch` sorts new files once they stop changing
+use colored::Colorize;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+use walkdir::WalkDir;
+
+use crate::config::config_processor::IrisConfig;
+use crate::core::sort::sort::{self, output_dirs, OutputDirs, SortOptions};
+use crate::core::sort::walk::walk_depth;
+use crate::logging;
+
+/// How often pending files are checked
+const TICK: Duration = Duration::from_millis(250);
+
+/// Runtime options of `iris watch`
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    // options of every sort run, `only` is filled in for each batch
+    pub sort: SortOptions,
+    // how long an entry has to stay untouched, with the same content, before it is sorted
+    pub debounce: Duration,
+    // changes to this file (and to the preset files) reload the config
+    pub config_path: PathBuf,
+}
+
+/// A file seen changing, waiting to settle before it is sorted
+#[derive(Debug)]
+struct Pending {
+    last_change: Instant,
+    // state at the last check, the entry is ready once two checks agree
+    snapshot: Option<Snapshot>,
+}
+
+/// What is compared between two checks of a pending entry. Folders are walked, as
+/// writes inside them raise no event when the target is watched non-recursively.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Snapshot {
+    // total size of the file, or of every file under the folder
+    size: u64,
+    entries: u64,
+    newest: Option<SystemTime>,
+}
+
+/// Watch `target` and sort new entries until the process is stopped.
+/// `reload` is called to load the config again whenever it changes on disk.
+pub fn watch<F>(
+    target: &Path,
+    mut config: IrisConfig,
+    reload: F,
+    options: &WatchOptions,
+) -> Result<(), Box<dyn Error>>
+where
+    F: Fn() -> Result<IrisConfig, String>,
+{
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+
+    let mut mode = recursive_mode(&config);
+    watcher.watch(target, mode)?;
+    let mut watched_dirs = config_dirs(&config, &options.config_path);
+    for dir in &watched_dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    // start from a sorted folder, then only handle what arrives.
+    // this run also refuses protected and project folders, which stops the watcher
+    sort::sort(target, &config, &options.sort)?;
+    if !logging::quiet() {
+        println!(
+            "{}",
+            format!("Watching {} (press Ctrl+C to stop)", target.display()).bright_green()
+        );
+    }
+
+    let mut outputs = output_dirs(target, &config);
+    let mut pending: HashMap<PathBuf, Pending> = HashMap::new();
+    let mut config_changed: Option<Instant> = None;
+
+    loop {
+        match rx.recv_timeout(TICK) {
+            Ok(Ok(event)) => {
+                let now = Instant::now();
+                for path in event.paths {
+                    let presets_path = config.general.presets_path.as_deref();
+                    if is_config_file(&path, &options.config_path, presets_path) {
+                        // reading the config on reload raises access events, ignore those
+                        if !matches!(event.kind, EventKind::Access(_)) {
+                            config_changed = Some(now);
+                        }
+                    } else if is_new_entry(&event.kind)
+                        && path.starts_with(target)
+                        && path != target
+                        && !is_sort_output(target, &outputs, &path)
+                    {
+                        log::trace!("change to {}", path.display());
+                        pending
+                            .entry(path)
+                            .and_modify(|p| p.last_change = now)
+                            .or_insert(Pending {
+                                last_change: now,
+                                snapshot: None,
+                            });
+                    }
+                }
+            }
+            Ok(Err(e)) => log::error!("Watch error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err("the file watcher stopped".into()),
+        }
+
+        // editors write a file in several steps, so wait for the changes to settle
+        if config_changed.is_some_and(|t| t.elapsed() >= options.debounce) {
+            config_changed = None;
+            match reload() {
+                Ok(new_config) => {
+                    config = new_config;
+                    if !logging::quiet() {
+                        println!("{}", "Config changed on disk, reloaded.".bright_green());
+                    }
+
+                    let new_mode = recursive_mode(&config);
+                    if new_mode != mode {
+                        watcher.unwatch(target)?;
+                        watcher.watch(target, new_mode)?;
+                        mode = new_mode;
+                    }
+                    for dir in config_dirs(&config, &options.config_path) {
+                        if !watched_dirs.contains(&dir) {
+                            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+                            watched_dirs.push(dir);
+                        }
+                    }
+                    outputs = output_dirs(target, &config);
+                }
+                Err(e) => log::error!("Config reload failed, keeping the previous config: {}", e),
+            }
+        }
+
+        // a file younger than min_age would be skipped by the sort and never seen again
+        let quiet = options.debounce.max(config.exclusions.min_age);
+        let ready = settled(&mut pending, quiet);
+        if !ready.is_empty() {
+            log::debug!("{} entries settled, sorting them", ready.len());
+            run_sort(target, &config, &options.sort, Some(ready));
+        }
+    }
+}
+
+/// Sort the whole target, or only the given entries of it
+fn run_sort(
+    target: &Path,
+    config: &IrisConfig,
+    options: &SortOptions,
+    only: Option<HashSet<PathBuf>>,
+) {
+    let options = SortOptions {
+        only,
+        ..options.clone()
+    };
+    // a failed run must not stop the watcher
+    if let Err(e) = sort::sort(target, config, &options) {
+        log::error!("Error: {}", e);
+    }
+}
+
+/// Nested folders only need watching when a preset sorts them
+fn recursive_mode(config: &IrisConfig) -> RecursiveMode {
+    if walk_depth(config) > 1 {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    }
+}
+
+/// Folders holding the config: the one of iris.toml and presets_path.
+/// Folders are watched rather than files because editors often replace the file.
+fn config_dirs(config: &IrisConfig, config_path: &Path) -> Vec<PathBuf> {
+    config_path
+        .parent()
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain(config.general.presets_path.clone())
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// iris.toml itself, or a preset file of presets_path
+fn is_config_file(path: &Path, config_path: &Path, presets_path: Option<&Path>) -> bool {
+    path == config_path
+        || (path.extension().is_some_and(|e| e == "toml")
+            && presets_path.is_some_and(|dir| path.parent() == Some(dir)))
+}
+
+/// Events of entries that appeared or are still being written
+fn is_new_entry(kind: &EventKind) -> bool {
+    matches!(kind, EventKind::Create(_) | EventKind::Modify(_))
+}
+
+/// Check whether `path` was moved into one of the output folders by the sort itself.
+/// The target is never an output folder, even for a template starting with a placeholder.
+fn is_sort_output(target: &Path, outputs: &OutputDirs, path: &Path) -> bool {
+    path.ancestors()
+        .take_while(|dir| *dir != target)
+        .any(|dir| outputs.contains(dir))
+}
+
+/// Take the pending entries that have settled: untouched for `debounce` and in the
+/// same state as at the previous check. Entries that disappeared are dropped.
+fn settled(pending: &mut HashMap<PathBuf, Pending>, debounce: Duration) -> HashSet<PathBuf> {
+    let now = Instant::now();
+    let mut ready = HashSet::new();
+    pending.retain(|path, entry| {
+        if now.duration_since(entry.last_change) < debounce {
+            return true;
+        }
+        let Some(snapshot) = snapshot(path) else {
+            return false;
+        };
+        if entry.snapshot == Some(snapshot) {
+            ready.insert(path.clone());
+            return false;
+        }
+        // first check, or still changing: wait for another quiet period
+        entry.snapshot = Some(snapshot);
+        entry.last_change = now;
+        true
+    });
+    ready
+}
+
+/// The current state of a pending entry, None once it is gone
+fn snapshot(path: &Path) -> Option<Snapshot> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    let mut snapshot = Snapshot {
+        size: metadata.len(),
+        entries: 1,
+        newest: metadata.modified().ok(),
+    };
+    if metadata.is_dir() {
+        // entries vanishing during the walk are left out, the next check sees the change
+        for metadata in WalkDir::new(path)
+            .min_depth(1)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter_map(|e| e.metadata().ok())
+        {
+            if metadata.is_file() {
+                snapshot.size += metadata.len();
+            }
+            snapshot.entries += 1;
+            snapshot.newest = snapshot.newest.max(metadata.modified().ok());
+        }
+    }
+    Some(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::config_processor::PresetConfig;
+    use tempfile::tempdir;
+
+    fn pending(path: &Path) -> HashMap<PathBuf, Pending> {
+        let mut pending = HashMap::new();
+        pending.insert(
+            path.to_path_buf(),
+            Pending {
+                last_change: Instant::now(),
+                snapshot: None,
+            },
+        );
+        pending
+    }
+
+    #[test]
+    fn test_file_settles_once_size_is_stable() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("movie.mkv");
+        fs::write(&file, "part").unwrap();
+        let mut pending = pending(&file);
+
+        // the first check only records the size
+        assert!(settled(&mut pending, Duration::ZERO).is_empty());
+        // still growing
+        fs::write(&file, "partial download").unwrap();
+        assert!(settled(&mut pending, Duration::ZERO).is_empty());
+        // unchanged since the last check
+        let ready = settled(&mut pending, Duration::ZERO);
+        assert!(ready.contains(&file));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_folder_settles_once_its_content_is_stable() {
+        let dir = tempdir().unwrap();
+        let folder = dir.path().join("album");
+        fs::create_dir_all(folder.join("disc 1")).unwrap();
+        fs::write(folder.join("disc 1/01.flac"), "part").unwrap();
+        let mut pending = pending(&folder);
+
+        assert!(settled(&mut pending, Duration::ZERO).is_empty());
+        // a file deep inside is still being written
+        fs::write(folder.join("disc 1/01.flac"), "partial track").unwrap();
+        assert!(settled(&mut pending, Duration::ZERO).is_empty());
+        // a new file appears
+        fs::write(folder.join("cover.jpg"), "").unwrap();
+        assert!(settled(&mut pending, Duration::ZERO).is_empty());
+        let ready = settled(&mut pending, Duration::ZERO);
+        assert!(ready.contains(&folder));
+    }
+
+    #[test]
+    fn test_recent_and_missing_files() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, "notes").unwrap();
+
+        // changed less than `debounce` ago
+        let mut waiting = pending(&file);
+        assert!(settled(&mut waiting, Duration::from_secs(60)).is_empty());
+        assert_eq!(waiting.len(), 1);
+
+        // removed before settling
+        let mut gone = pending(&dir.path().join("missing.txt"));
+        assert!(settled(&mut gone, Duration::ZERO).is_empty());
+        assert!(gone.is_empty());
+    }
+
+    #[test]
+    fn test_sort_output_filter() {
+        let target = Path::new("/home/me/Downloads");
+        let preset = |name: &str, path: &str| PresetConfig {
+            name: name.to_string(),
+            enabled: true,
+            relative_path: Some(PathBuf::from(path)),
+            ..Default::default()
+        };
+        let config = IrisConfig {
+            presets: vec![
+                preset("photos", "{year}/{month}"),
+                preset("docs", "documents"),
+            ],
+            ..Default::default()
+        };
+        let outputs = output_dirs(target, &config);
+
+        // a template starting with a placeholder does not hide the whole target
+        assert!(!is_sort_output(target, &outputs, &target.join("notes.txt")));
+        assert!(!is_sort_output(
+            target,
+            &outputs,
+            &target.join("inbox/notes.txt")
+        ));
+        assert!(is_sort_output(
+            target,
+            &outputs,
+            &target.join("2024/05/photo.jpg")
+        ));
+        assert!(is_sort_output(target, &outputs, &target.join("documents")));
+        assert!(is_sort_output(
+            target,
+            &outputs,
+            &target.join("documents/notes.txt")
+        ));
+    }
+
+    #[test]
+    fn test_is_config_file() {
+        let config_path = Path::new("/home/me/.config/iris/iris.toml");
+        let presets = Some(Path::new("/home/me/.config/iris/presets"));
+        assert!(is_config_file(config_path, config_path, presets));
+        assert!(is_config_file(
+            Path::new("/home/me/.config/iris/presets/images.toml"),
+            config_path,
+            presets
+        ));
+        // other files next to iris.toml are not config
+        assert!(!is_config_file(
+            Path::new("/home/me/.config/iris/other.toml"),
+            config_path,
+            presets
+        ));
+        assert!(!is_config_file(
+            Path::new("/home/me/.config/iris/presets/notes.txt"),
+            config_path,
+            presets
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+use chrono::Local;
+use colored::Colorize;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::config::config_processor::GeneralConfig;
+
+/// Level used until the config is loaded, and when it does not set log_level
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Warn;
+
+/// Size at which the log file is rotated
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Rotated files kept next to the log file, ".1" being the most recent
+const KEPT_LOGS: usize = 3;
+
+struct Logger {
+    state: Mutex<State>,
+}
+
+struct State {
+    // level forced by -q/-v/-vv, it wins over log_level
+    cli_level: Option<LevelFilter>,
+    file: Option<LogFile>,
+}
+
+/// The open log file and its current size, to know when to rotate it
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+/// Set by -q: progress messages are not printed, warnings and errors still are
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+static LOGGER: Logger = Logger {
+    state: Mutex::new(State {
+        cli_level: None,
+        file: None,
+    }),
+};
+
+/// Install the logger. `cli_level` comes from -q/-v/-vv and overrides the config,
+/// `quiet` hides the progress messages of every command.
+pub fn init(cli_level: Option<LevelFilter>, quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    // only fails if a logger is already installed, which keeps that one
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(cli_level.unwrap_or(DEFAULT_LEVEL));
+    if let Ok(mut state) = LOGGER.state.lock() {
+        state.cli_level = cli_level;
+    }
+}
+
+/// Whether progress messages are hidden with -q. Output a command exists for (a dry run
+/// plan, history, reports for scripts...) is printed anyway.
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Apply log_level and log_file from [general], once the config is loaded (or reloaded)
+pub fn apply_config(general: &GeneralConfig, log_path: &Path) {
+    let Ok(mut state) = LOGGER.state.lock() else {
+        return;
+    };
+    let level = state
+        .cli_level
+        .or(general.log_level)
+        .unwrap_or(DEFAULT_LEVEL);
+    log::set_max_level(level);
+
+    if !general.log_file {
+        state.file = None;
+    } else if state.file.as_ref().is_none_or(|f| f.path != log_path) {
+        state.file = match LogFile::open(log_path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                drop(state);
+                log::warn!("cannot open the log file '{}': {}", log_path.display(), e);
+                return;
+            }
+        };
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // dependencies log too, only the records of iris are shown
+        metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) || record.level() > log::max_level() {
+            return;
+        }
+        let message = record.args().to_string();
+        match record.level() {
+            Level::Error => eprintln!("{}", message.red()),
+            Level::Warn => eprintln!("{}", format!("Warning: {}", message).yellow()),
+            Level::Info => eprintln!("{}", message),
+            level => eprintln!(
+                "{}",
+                format!("{}: {}", level.as_str().to_lowercase(), message).dimmed()
+            ),
+        }
+
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(file) = &mut state.file {
+                let line = format!(
+                    "{} {:<5} {}\n",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    record.level(),
+                    message
+                );
+                // a log file that cannot be written to is given up on, not retried
+                if file.write(&line).is_err() {
+                    state.file = None;
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(file) = &mut state.file {
+                let _ = file.file.flush();
+            }
+        }
+    }
+}
+
+impl LogFile {
+    fn open(path: &Path) -> io::Result<LogFile> {
+        if fs::metadata(path).is_ok_and(|m| m.len() >= MAX_LOG_SIZE) {
+            rotate(path)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(LogFile {
+            path: path.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    fn write(&mut self, line: &str) -> io::Result<()> {
+        // long running commands like `iris watch` rotate while they run
+        if self.size + line.len() as u64 > MAX_LOG_SIZE && self.size > 0 {
+            rotate(&self.path)?;
+            *self = LogFile::open(&self.path)?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Shift iris.log to iris.log.1, iris.log.1 to iris.log.2... dropping the oldest
+fn rotate(path: &Path) -> io::Result<()> {
+    for n in (1..KEPT_LOGS).rev() {
+        let older = rotated_path(path, n);
+        if older.exists() {
+            fs::rename(&older, rotated_path(path, n + 1))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rotate_keeps_the_latest_logs() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("iris.log");
+        for run in 1..=5 {
+            fs::write(&path, format!("run {}", run)).unwrap();
+            rotate(&path).unwrap();
+        }
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "run 5");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 3)).unwrap(), "run 3");
+        assert!(!rotated_path(&path, 4).exists());
+    }
+
+    #[test]
+    fn test_large_log_is_rotated_on_open() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("iris.log");
+        fs::write(&path, vec![b'x'; MAX_LOG_SIZE as usize]).unwrap();
+
+        let mut log = LogFile::open(&path).unwrap();
+        assert_eq!(log.size, 0);
+        log.write("fresh\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fresh\n");
+        assert!(rotated_path(&path, 1).exists());
+    }
+}
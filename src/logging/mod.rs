@@ -0,0 +1,6 @@
+// This module routes the diagnostics of every command through the `log` facade: to stderr
+// at the level picked by -q/-v/-vv or log_level, and optionally to iris.log in the config
+// directory
+mod logger;
+
+pub use logger::{apply_config, init, quiet};
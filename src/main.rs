@@ -1,12 +1,13 @@
 mod cli;
 mod config;
 mod core;
+mod logging;
 mod paths;
 mod platform;
 
 use clap::Parser;
 
-use cli::cli_parser::ConfigAction;
+use cli::cli_parser::{ConfigAction, HistoryAction, PresetsAction};
 
 #[cfg(target_os = "windows")]
 use cli::cli_parser::ContextAction;
@@ -18,7 +19,13 @@ use config::{
     config_validator,
 };
 use config_processor::IrisConfig;
-use core::{resolver::target_resolver, sort::sort};
+use core::{
+    history::history, presets::presets, resolver::target_resolver, sort::sort, undo::undo,
+    watch::watch,
+};
+
+/// Exit code of `iris sort` when the run went through but some moves failed
+const EXIT_MOVES_FAILED: i32 = 3;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // config file path
@@ -26,6 +33,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // check if config file path exists, if not initialize a default file
     if !config_path.exists() {
         config_init::init_defaults(&config_path)?;
+        config_init::init_presets(&config_init::default_presets_dir(), false)?;
     }
 
     let mut args: Vec<String> = std::env::args().collect();
@@ -37,10 +45,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // parse args using the overridden command
     let cli = Cli::parse_from(&args);
+    logging::init(cli.log_level(), cli.quiet);
 
     match &cli.command {
         // config commands do NOT require a valid config
         Commands::Config { action } => match action {
+            ConfigAction::Init { force } => {
+                handle_result(config_init::init_config(*force));
+            }
             ConfigAction::Show => {
                 handle_result(config_show::show_config());
             }
@@ -51,6 +63,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 handle_result(config_reset::reset_config(*noconfirm));
             }
         },
+        Commands::Undo { run_id } => {
+            let journal_dir = paths::config_path::get_journal_dir();
+            if handle_result(undo::undo(&journal_dir, run_id.as_deref())).is_none() {
+                std::process::exit(1);
+            }
+        }
+        Commands::History { action, filter } => {
+            let journal_dir = paths::config_path::get_journal_dir();
+            let history_filter = history::HistoryFilter {
+                since: filter.since,
+                until: filter.until,
+                preset: filter.preset.clone(),
+                path: filter.path.clone(),
+            };
+            let result = match action {
+                Some(HistoryAction::Show { run_id }) => {
+                    history::show_run(&journal_dir, run_id, &history_filter, filter.json)
+                }
+                None => history::list_history(&journal_dir, &history_filter, filter.json),
+            };
+            if handle_result(result).is_none() {
+                std::process::exit(1);
+            }
+        }
+        Commands::Presets { action } => match action {
+            PresetsAction::Conflicts => {
+                presets::show_conflicts(&load_config());
+            }
+        },
         Commands::Update => {
             println!("TODO");
         }
@@ -69,36 +110,57 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         Commands::Completions { action } => {
             use cli::shell_completion::handle_completion;
-            handle_completion(&action);
+            handle_completion(action);
         }
 
         // commands that require a valid config
-        Commands::Sort { path } => {
-            // parse the config
-            let value = match config_parser::parse_config() {
-                Ok(v) => v,
+        Commands::Sort {
+            path,
+            dry_run,
+            force,
+            jobs,
+            allow_protected,
+            output,
+        } => {
+            let iris_config = load_config();
+
+            // resolve the actual target path based on config and CLI args
+            let target_path = match target_resolver::resolve_target(&iris_config, path.as_ref()) {
+                Ok(p) => p,
                 Err(e) => {
-                    eprintln!("failed to parse config file. error: {}", e);
+                    eprintln!("error: {}", e);
                     std::process::exit(1);
                 }
             };
-
-            // validate the config
-            if let Err(err_msg) = config_validator::validate_config(&value) {
-                eprintln!("Config file is invalid. Error: {}", err_msg);
-                std::process::exit(1);
-            }
-
-            // process the config into IrisConfig struct
-            let iris_config = match IrisConfig::from_value(&value) {
-                Ok(config) => config,
+            let options = sort::SortOptions {
+                dry_run: *dry_run,
+                journal_dir: Some(paths::config_path::get_journal_dir()),
+                verbose: cli.verbose > 0,
+                only: None,
+                force: *force,
+                allow_protected: *allow_protected,
+                output: *output,
+                jobs: jobs.map(|n| n as usize),
+            };
+            match sort::sort(target_path.as_path(), &iris_config, &options) {
+                Ok(summary) if summary.failed > 0 => std::process::exit(EXIT_MOVES_FAILED),
+                Ok(_) => {}
                 Err(e) => {
-                    eprintln!("error processing config:\n {}", e);
+                    eprintln!("{}", format!("Error: {}", e).red());
                     std::process::exit(1);
                 }
-            };
+            }
+        }
+        Commands::Watch {
+            path,
+            debounce,
+            dry_run,
+            force,
+            jobs,
+            allow_protected,
+        } => {
+            let iris_config = load_config();
 
-            // resolve the actual target path based on config and CLI args
             let target_path = match target_resolver::resolve_target(&iris_config, path.as_ref()) {
                 Ok(p) => p,
                 Err(e) => {
@@ -106,7 +168,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     std::process::exit(1);
                 }
             };
-            if let Err(e) = sort::sort(target_path.as_path(), &iris_config) {
+            let options = watch::WatchOptions {
+                sort: sort::SortOptions {
+                    dry_run: *dry_run,
+                    journal_dir: Some(paths::config_path::get_journal_dir()),
+                    verbose: cli.verbose > 0,
+                    only: None,
+                    force: *force,
+                    allow_protected: *allow_protected,
+                    output: Default::default(),
+                    jobs: jobs.map(|n| n as usize),
+                },
+                debounce: std::time::Duration::from_secs(*debounce),
+                config_path,
+            };
+            if let Err(e) = watch::watch(&target_path, iris_config, try_load_config, &options) {
                 eprintln!("{}", format!("Error: {}", e).red());
                 std::process::exit(1);
             }
@@ -116,6 +192,36 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Parse, validate and process the config, exiting with an error message if any step fails
+fn load_config() -> IrisConfig {
+    match try_load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parse, validate and process the config
+fn try_load_config() -> Result<IrisConfig, String> {
+    // parse the config
+    let parsed = config_parser::parse_config()
+        .map_err(|e| format!("failed to parse config file. error: {}", e))?;
+
+    // validate the config
+    config_validator::validate_config(&parsed.value)
+        .map_err(|e| format!("Config file is invalid. Error: {}", e))?;
+
+    // process the config into IrisConfig struct
+    let config = IrisConfig::from_value(&parsed.value, &parsed.preset_order)
+        .map_err(|e| format!("error processing config:\n {}", e))?;
+
+    // log_level and log_file only take effect once the config is known
+    logging::apply_config(&config.general, &paths::config_path::get_log_path());
+    Ok(config)
+}
+
 fn handle_result<T, E: std::fmt::Display>(res: Result<T, E>) -> Option<T> {
     match res {
         Ok(val) => Some(val),
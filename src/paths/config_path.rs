@@ -16,9 +16,7 @@ pub fn get_config_dir() -> PathBuf {
         panic!("Unsupported Environment");
     };
 
-    if cfg!(target_os = "windows") {
-        dir.join("Iris")
-    } else if cfg!(target_os = "macos") {
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
         dir.join("Iris")
     } else {
         dir.join("iris")
@@ -32,6 +30,16 @@ pub fn get_config_path() -> PathBuf {
     dir.join("iris.toml")
 }
 
+/// Get the directory holding the journals of past sort runs
+pub fn get_journal_dir() -> PathBuf {
+    get_config_dir().join("journal")
+}
+
+/// Get the path of the log file written when log_file = true
+pub fn get_log_path() -> PathBuf {
+    get_config_dir().join("iris.log")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -48,4 +56,11 @@ mod tests {
         let path = get_config_path();
         assert!(path.ends_with("iris.toml"));
     }
+
+    #[test]
+    fn test_get_journal_dir() {
+        let dir = get_journal_dir();
+        assert!(dir.starts_with(get_config_dir()));
+        assert!(dir.ends_with("journal"));
+    }
 }